@@ -0,0 +1,191 @@
+//! Anchor text dictionary: which articles a piece of link text refers to, and how often.
+//!
+//! This is the usual "surface form → entity" prior for entity linking. Labels are normalized with
+//! [normalize_label] and targets with [normalize_title], so `[[paris|Paris]]` and
+//! `[[Paris#History|paris]]` count as the same anchor and entity.
+
+use std::collections::HashMap;
+
+use html_escape::decode_html_entities;
+use serde::Serialize;
+
+use crate::extractors::links::{LinkKind, Page};
+use crate::wiki::normalize_title;
+
+#[cfg(test)]
+mod tests;
+
+/// Counts of label → target pairs
+#[derive(Default)]
+pub struct AnchorDictionary {
+    counts: HashMap<String, HashMap<String, u64>>,
+}
+
+/// An entry of the label dictionary
+#[derive(Serialize)]
+pub struct LabelEntry<'a> {
+    pub label: &'a str,
+    /// Number of links with this label
+    pub count: u64,
+    /// Targets by decreasing count
+    pub targets: Vec<TargetCount<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct TargetCount<'a> {
+    pub target: &'a str,
+    pub count: u64,
+    /// Share of the links with this label that point at this target
+    pub probability: f64,
+}
+
+/// An entry of the target dictionary
+#[derive(Serialize)]
+pub struct TargetEntry<'a> {
+    pub target: &'a str,
+    /// Number of links to this target
+    pub count: u64,
+    /// Labels by decreasing count
+    pub labels: Vec<LabelCount<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct LabelCount<'a> {
+    pub label: &'a str,
+    pub count: u64,
+}
+
+impl AnchorDictionary {
+    /// Count the article links of a page
+    pub fn add_page(&mut self, page: &Page) {
+        for link in &page.links {
+            if link.kind != LinkKind::Article {
+                continue;
+            }
+            let label = normalize_label(&link.label);
+            let target = normalize_title(&link.target);
+            if label.is_empty() || target.is_empty() {
+                continue;
+            }
+
+            *self
+                .counts
+                .entry(label)
+                .or_default()
+                .entry(target)
+                .or_default() += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: AnchorDictionary) {
+        for (label, targets) in other.counts {
+            let counts = self.counts.entry(label).or_default();
+            for (target, count) in targets {
+                *counts.entry(target).or_default() += count;
+            }
+        }
+    }
+
+    /// Drop label → target pairs seen fewer than `min_count` times, and labels left without
+    /// targets
+    pub fn prune(&mut self, min_count: u64) {
+        for targets in self.counts.values_mut() {
+            targets.retain(|_, count| *count >= min_count);
+        }
+        self.counts.retain(|_, targets| !targets.is_empty());
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Labels in alphabetical order, each with the distribution of its targets
+    pub fn labels(&self) -> Vec<LabelEntry<'_>> {
+        let mut entries: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(label, targets)| {
+                let count = targets.values().sum();
+                let mut targets: Vec<_> = targets
+                    .iter()
+                    .map(|(target, &n)| TargetCount {
+                        target,
+                        count: n,
+                        probability: n as f64 / count as f64,
+                    })
+                    .collect();
+                targets.sort_by(|a, b| b.count.cmp(&a.count).then(a.target.cmp(b.target)));
+                LabelEntry {
+                    label,
+                    count,
+                    targets,
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.label);
+        entries
+    }
+
+    /// Targets in alphabetical order, each with the labels used to link to it
+    pub fn targets(&self) -> Vec<TargetEntry<'_>> {
+        let mut by_target: HashMap<&str, Vec<LabelCount>> = HashMap::new();
+        for (label, targets) in &self.counts {
+            for (target, &count) in targets {
+                by_target
+                    .entry(target)
+                    .or_default()
+                    .push(LabelCount { label, count });
+            }
+        }
+
+        let mut entries: Vec<_> = by_target
+            .into_iter()
+            .map(|(target, mut labels)| {
+                labels.sort_by(|a, b| b.count.cmp(&a.count).then(a.label.cmp(b.label)));
+                TargetEntry {
+                    target,
+                    count: labels.iter().map(|label| label.count).sum(),
+                    labels,
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.target);
+        entries
+    }
+}
+
+/// Lowercase, decode entities, drop bold and italics and collapse whitespace
+pub fn normalize_label(label: &str) -> String {
+    let label = decode_html_entities(label);
+    strip_emphasis(&label)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+// Drop the runs of two or more apostrophes that mark italics (`''`), bold (`'''`) and both
+// (`'''''`), keeping single apostrophes
+fn strip_emphasis(label: &str) -> String {
+    let mut stripped = String::with_capacity(label.len());
+    let mut apostrophes = 0;
+    for c in label.chars() {
+        if c == '\'' {
+            apostrophes += 1;
+            continue;
+        }
+        if apostrophes == 1 {
+            stripped.push('\'');
+        }
+        apostrophes = 0;
+        stripped.push(c);
+    }
+    if apostrophes == 1 {
+        stripped.push('\'');
+    }
+    stripped
+}
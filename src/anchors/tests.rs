@@ -0,0 +1,72 @@
+use super::{normalize_label, AnchorDictionary};
+use crate::extractors::links::{Link, LinkKind, Page};
+use crate::wiki::normalize_title;
+
+fn page(links: &[(&str, &str)]) -> Page {
+    Page {
+        title: "Page".to_owned(),
+        links: links
+            .iter()
+            .map(|(target, label)| Link {
+                target: target.to_string(),
+                label: label.to_string(),
+                kind: LinkKind::classify(target),
+                template: None,
+                parameter: None,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn normalization() {
+    assert_eq!(normalize_label("  ''The''   Beatles "), "the beatles");
+    assert_eq!(normalize_label("Guns N&#x27; Roses"), "guns n' roses");
+    assert_eq!(normalize_label("'''Bold'''"), "bold");
+    assert_eq!(
+        normalize_label("'''''Bold''''' and '''''italic'''''"),
+        "bold and italic"
+    );
+    assert_eq!(normalize_label("''Rock 'n' roll''"), "rock 'n' roll");
+    assert_eq!(normalize_label("Hawai'i'"), "hawai'i'");
+    assert_eq!(normalize_title("paris#History"), "Paris");
+    assert_eq!(normalize_title("New_York  City"), "New York City");
+    assert_eq!(normalize_title("émile Zola"), "Émile Zola");
+    assert_eq!(normalize_title("en:Paris"), "Paris");
+    assert_eq!(normalize_title("fr:Paris"), "Fr:Paris");
+}
+
+#[test]
+fn dictionary() {
+    let mut dictionary = AnchorDictionary::default();
+    dictionary.add_page(&page(&[
+        ("Paris", "Paris"),
+        ("paris#History", "paris"),
+        ("Paris, Texas", "Paris"),
+        ("Category:Cities", "Category:Cities"),
+    ]));
+    let mut other = AnchorDictionary::default();
+    other.add_page(&page(&[("Paris", "City of Light")]));
+    dictionary.merge(other);
+
+    let labels = dictionary.labels();
+    assert_eq!(labels.len(), 2);
+    assert_eq!(labels[0].label, "city of light");
+    assert_eq!(labels[1].label, "paris");
+    assert_eq!(labels[1].count, 3);
+    let targets: Vec<_> = labels[1]
+        .targets
+        .iter()
+        .map(|t| (t.target, t.count))
+        .collect();
+    assert_eq!(targets, vec![("Paris", 2), ("Paris, Texas", 1)]);
+
+    let targets = dictionary.targets();
+    assert_eq!(targets[0].target, "Paris");
+    assert_eq!(targets[0].count, 3);
+    let labels: Vec<_> = targets[0].labels.iter().map(|l| l.label).collect();
+    assert_eq!(labels, vec!["paris", "city of light"]);
+
+    dictionary.prune(2);
+    assert_eq!(dictionary.len(), 1);
+}
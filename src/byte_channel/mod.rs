@@ -0,0 +1,109 @@
+//! A channel whose capacity is a number of bytes instead of a number of items.
+//!
+//! Wikipedia pages range from a few bytes to several megabytes, so a channel holding N pages can
+//! need anywhere from kilobytes to gigabytes. Bounding by bytes keeps memory usage predictable.
+
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+
+#[cfg(test)]
+mod tests;
+
+struct Usage {
+    bytes: usize,
+    items: usize,
+}
+
+struct Shared {
+    capacity: usize,
+    usage: Mutex<Usage>,
+    released: Condvar,
+}
+
+pub struct Sender<T> {
+    sender: mpsc::Sender<(T, usize)>,
+    shared: Arc<Shared>,
+}
+
+pub struct Receiver<T> {
+    receiver: mpsc::Receiver<(T, usize)>,
+    shared: Arc<Shared>,
+}
+
+/// Create a channel that holds at most `capacity` bytes.
+///
+/// A single item larger than `capacity` is still accepted once the channel is empty, so an
+/// oversized page slows the pipeline down instead of deadlocking it.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        capacity,
+        usage: Mutex::new(Usage { bytes: 0, items: 0 }),
+        released: Condvar::new(),
+    });
+    let (sender, receiver) = mpsc::channel();
+
+    (
+        Sender {
+            sender,
+            shared: shared.clone(),
+        },
+        Receiver { receiver, shared },
+    )
+}
+
+impl<T> Sender<T> {
+    /// Send `item`, accounted as `size` bytes. Blocks until there is room for it.
+    pub fn send(&self, item: T, size: usize) -> Result<(), mpsc::SendError<T>> {
+        {
+            let mut usage = self.shared.usage.lock().unwrap();
+            while usage.items > 0 && usage.bytes + size > self.shared.capacity {
+                usage = self.shared.released.wait(usage).unwrap();
+            }
+            usage.bytes += size;
+            usage.items += 1;
+        }
+
+        self.sender.send((item, size)).map_err(|e| {
+            self.shared.release(size);
+            mpsc::SendError(e.0 .0)
+        })
+    }
+
+    /// Number of items waiting in the channel
+    pub fn depth(&self) -> usize {
+        self.shared.usage.lock().unwrap().items
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Receiver<T> {
+    pub fn recv(&self) -> Result<T, mpsc::RecvError> {
+        let (item, size) = self.receiver.recv()?;
+        self.shared.release(size);
+        Ok(item)
+    }
+}
+
+impl<T> Iterator for Receiver<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.recv().ok()
+    }
+}
+
+impl Shared {
+    fn release(&self, size: usize) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.bytes -= size;
+        usage.items -= 1;
+        self.released.notify_all();
+    }
+}
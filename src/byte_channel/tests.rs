@@ -0,0 +1,30 @@
+use std::{thread, time::Duration};
+
+use super::channel;
+
+#[test]
+fn blocks_when_full() {
+    let (sender, receiver) = channel::<u8>(10);
+    sender.send(1, 6).unwrap();
+    sender.send(2, 4).unwrap();
+    assert_eq!(sender.depth(), 2);
+
+    let handle = {
+        let sender = sender.clone();
+        thread::spawn(move || sender.send(3, 5).unwrap())
+    };
+    thread::sleep(Duration::from_millis(100));
+    assert!(!handle.is_finished());
+
+    assert_eq!(receiver.recv().unwrap(), 1);
+    handle.join().unwrap();
+    assert_eq!(sender.depth(), 2);
+}
+
+#[test]
+fn accepts_oversized_item_when_empty() {
+    let (sender, receiver) = channel::<u8>(10);
+    sender.send(1, 100).unwrap();
+    drop(sender);
+    assert_eq!(receiver.collect::<Vec<_>>(), vec![1]);
+}
@@ -0,0 +1,188 @@
+//! The category hierarchy and category membership of articles, built from the output of the
+//! categories extractor.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    fs::File,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::extractors::categories::{category_name, Page};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Default)]
+pub struct CategoryGraph {
+    /// Category → its parent categories
+    parents: HashMap<String, Vec<String>>,
+    /// Category → articles in it
+    articles: HashMap<String, Vec<String>>,
+}
+
+/// A category with its neighbours in the hierarchy
+#[derive(Serialize)]
+pub struct CategoryEntry<'a> {
+    pub category: &'a str,
+    pub parents: &'a [String],
+    pub subcategories: Vec<&'a str>,
+    pub articles: &'a [String],
+}
+
+// A line of the entries file, without the subcategories, which follow from the parents
+#[derive(Deserialize)]
+struct StoredEntry {
+    category: String,
+    parents: Vec<String>,
+    articles: Vec<String>,
+}
+
+impl CategoryGraph {
+    /// Load the JSON lines file of [CategoryGraph::entries] written by `links category-graph`
+    pub fn open(filename: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(filename)?))
+    }
+
+    /// Read [CategoryGraph::entries] as JSON lines
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let mut graph = CategoryGraph::default();
+        for line in reader.lines() {
+            let entry: StoredEntry = serde_json::from_str(&line?)?;
+            if !entry.parents.is_empty() {
+                graph.parents.insert(entry.category.clone(), entry.parents);
+            }
+            if !entry.articles.is_empty() {
+                graph.articles.insert(entry.category, entry.articles);
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn add_page(&mut self, page: Page) {
+        let names = page.categories.into_iter().map(|category| category.name);
+        match category_name(&page.title) {
+            Some(category) => {
+                self.parents
+                    .entry(category.to_owned())
+                    .or_default()
+                    .extend(names);
+            }
+            None => {
+                for name in names {
+                    self.articles
+                        .entry(name)
+                        .or_default()
+                        .push(page.title.clone());
+                }
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: CategoryGraph) {
+        for (category, parents) in other.parents {
+            self.parents.entry(category).or_default().extend(parents);
+        }
+        for (category, articles) in other.articles {
+            self.articles.entry(category).or_default().extend(articles);
+        }
+    }
+
+    /// Child categories of every category
+    pub fn subcategories(&self) -> HashMap<&str, Vec<&str>> {
+        let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (child, parents) in &self.parents {
+            for parent in parents {
+                children.entry(parent).or_default().push(child);
+            }
+        }
+        for list in children.values_mut() {
+            list.sort_unstable();
+        }
+        children
+    }
+
+    /// Every category that has a page, a parent, a child or an article, in alphabetical order
+    pub fn entries(&self) -> Vec<CategoryEntry<'_>> {
+        let mut subcategories = self.subcategories();
+        let names: BTreeSet<&str> = self
+            .parents
+            .iter()
+            .flat_map(|(category, parents)| {
+                std::iter::once(category.as_str()).chain(parents.iter().map(String::as_str))
+            })
+            .chain(self.articles.keys().map(String::as_str))
+            .collect();
+
+        names
+            .into_iter()
+            .map(|category| CategoryEntry {
+                category,
+                parents: self.parents.get(category).map_or(&[], Vec::as_slice),
+                subcategories: subcategories.remove(category).unwrap_or_default(),
+                articles: self.articles.get(category).map_or(&[], Vec::as_slice),
+            })
+            .collect()
+    }
+
+    /// Categories below `root` down to `depth` levels of subcategories, with their distance from
+    /// `root`. The hierarchy has cycles, so every category is visited once.
+    pub fn descendants(&self, root: &str, depth: usize) -> BTreeMap<&str, usize> {
+        let subcategories = self.subcategories();
+        let mut distances = BTreeMap::new();
+        let mut queue = VecDeque::new();
+
+        let root = match self.known(root) {
+            Some(root) => root,
+            None => return distances,
+        };
+        distances.insert(root, 0);
+        queue.push_back(root);
+
+        while let Some(category) = queue.pop_front() {
+            let distance = distances[category];
+            if distance == depth {
+                continue;
+            }
+            for &child in subcategories.get(category).into_iter().flatten() {
+                if !distances.contains_key(child) {
+                    distances.insert(child, distance + 1);
+                    queue.push_back(child);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Articles in `root` or any of its subcategories down to `depth` levels
+    pub fn articles_under(&self, root: &str, depth: usize) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        let mut articles = Vec::new();
+        for category in self.descendants(root, depth).into_keys() {
+            for article in self.articles.get(category).into_iter().flatten() {
+                if seen.insert(article.as_str()) {
+                    articles.push(article.as_str());
+                }
+            }
+        }
+        articles
+    }
+
+    // The name of `category` as stored in the graph
+    fn known(&self, category: &str) -> Option<&str> {
+        if let Some((name, _)) = self.parents.get_key_value(category) {
+            return Some(name);
+        }
+        if let Some((name, _)) = self.articles.get_key_value(category) {
+            return Some(name);
+        }
+        self.parents
+            .values()
+            .flatten()
+            .find(|parent| *parent == category)
+            .map(String::as_str)
+    }
+}
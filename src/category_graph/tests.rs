@@ -0,0 +1,101 @@
+use super::CategoryGraph;
+use crate::extractors::categories::{Category, Page};
+
+fn page(title: &str, categories: &[&str]) -> Page {
+    Page {
+        title: title.to_owned(),
+        categories: categories
+            .iter()
+            .map(|name| Category {
+                name: name.to_string(),
+                sort_key: None,
+            })
+            .collect(),
+    }
+}
+
+fn graph() -> CategoryGraph {
+    let mut graph = CategoryGraph::default();
+    graph.add_page(page("Category:Cities in France", &["Cities", "France"]));
+    graph.add_page(page("Category:Cities", &["Human settlements"]));
+    // A cycle, which real category trees have
+    graph.add_page(page("Category:Human settlements", &["Cities"]));
+
+    let mut other = CategoryGraph::default();
+    other.add_page(page("Paris", &["Cities in France", "Capitals in Europe"]));
+    other.add_page(page("Lyon", &["Cities in France"]));
+    other.add_page(page("Tokyo", &["Cities"]));
+    graph.merge(other);
+    graph
+}
+
+#[test]
+fn entries() {
+    let graph = graph();
+    let entries = graph.entries();
+    let names: Vec<_> = entries.iter().map(|entry| entry.category).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Capitals in Europe",
+            "Cities",
+            "Cities in France",
+            "France",
+            "Human settlements"
+        ]
+    );
+
+    let cities = &entries[1];
+    assert_eq!(cities.parents, ["Human settlements"]);
+    assert_eq!(
+        cities.subcategories,
+        vec!["Cities in France", "Human settlements"]
+    );
+    assert_eq!(cities.articles, ["Tokyo"]);
+}
+
+#[test]
+fn descendants() {
+    let graph = graph();
+    let descendants: Vec<_> = graph
+        .descendants("Human settlements", 5)
+        .into_iter()
+        .collect();
+    assert_eq!(
+        descendants,
+        vec![
+            ("Cities", 1),
+            ("Cities in France", 2),
+            ("Human settlements", 0)
+        ]
+    );
+
+    let mut articles = graph.articles_under("Cities", 1);
+    articles.sort_unstable();
+    assert_eq!(articles, vec!["Lyon", "Paris", "Tokyo"]);
+    assert_eq!(graph.articles_under("Cities", 0), vec!["Tokyo"]);
+    assert!(graph.articles_under("Unknown", 3).is_empty());
+}
+
+#[test]
+fn read_entries() {
+    let graph = graph();
+    let mut lines = String::new();
+    for entry in graph.entries() {
+        lines.push_str(&serde_json::to_string(&entry).unwrap());
+        lines.push('\n');
+    }
+
+    let read = CategoryGraph::read(lines.as_bytes()).unwrap();
+    let names: Vec<_> = read.entries().iter().map(|entry| entry.category).collect();
+    assert_eq!(names.len(), 5);
+    assert_eq!(
+        read.descendants("Human settlements", 5),
+        graph.descendants("Human settlements", 5)
+    );
+    let mut articles = read.articles_under("Human settlements", 2);
+    articles.sort_unstable();
+    assert_eq!(articles, vec!["Lyon", "Paris", "Tokyo"]);
+
+    assert!(CategoryGraph::read("{\"category\": 1}\n".as_bytes()).is_err());
+}
@@ -0,0 +1,165 @@
+//! Reading the data and index files written by the parser.
+//!
+//! The data file is a sequence of JSON pages, and every line of the index file is
+//! `offset: title` for the page starting at `offset`.
+
+use std::{
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    time::SystemTime,
+};
+
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+
+use crate::par_file::{ParFile, ParFileConfig};
+use crate::progress::{Progress, ProgressReporter};
+
+#[derive(Debug)]
+pub struct PageIndex {
+    pub title: String,
+    pub idx: usize,
+    pub start_offset: u64,
+    pub end_offset: Option<u64>,
+}
+
+pub fn read_index_file(filename: &str, par_file_config: ParFileConfig) -> Vec<PageIndex> {
+    const BUF_SIZE: usize = 300 * 1024 * 1024;
+
+    println!("Reading index ..");
+    let mut index_buf = String::with_capacity(BUF_SIZE);
+
+    let index_file = ParFile::with_config(filename.to_owned(), par_file_config);
+    BufReader::new(index_file)
+        .read_to_string(&mut index_buf)
+        .unwrap();
+
+    println!("Processing index ..");
+    let index_list: Vec<_> = index_buf
+        .lines()
+        .map(|line| {
+            let (start_offset, name) = line.split_once(":").unwrap();
+            let start_offset: u64 = start_offset.parse().unwrap();
+            let name = name.trim().to_owned();
+            (name, start_offset)
+        })
+        .collect();
+
+    println!("Got index list");
+    let mut end_offsets: Vec<Option<u64>> = index_list
+        .iter()
+        .map(|(_, start_offset)| Some(*start_offset))
+        .collect();
+
+    println!("Got end offsets");
+    end_offsets.push(None);
+
+    let index_map: Vec<PageIndex> = index_list
+        .into_iter()
+        .zip(end_offsets[1..].iter())
+        .enumerate()
+        .map(|(i, ((name, start_offset), end_offset))| PageIndex {
+            title: name,
+            idx: i,
+            start_offset,
+            end_offset: *end_offset,
+        })
+        .collect();
+
+    println!("Done");
+    println!();
+
+    index_map
+}
+
+/// Read every page of the data file in index order, parse them in parallel and fold them into
+/// one value per thread, which are then combined with `reduce`.
+pub fn fold_pages<P, A>(
+    filename: &str,
+    index_list: &[PageIndex],
+    par_file_config: ParFileConfig,
+    mut progress_reporter: Box<dyn ProgressReporter>,
+    identity: impl Fn() -> A + Send + Sync,
+    fold: impl Fn(A, &PageIndex, P) -> A + Send + Sync,
+    reduce: impl Fn(A, A) -> A + Send + Sync,
+) -> A
+where
+    P: DeserializeOwned,
+    A: Send,
+{
+    let file = File::open(filename).unwrap();
+    let file_size = file.metadata().unwrap().len();
+    let mut i = 0;
+
+    let mut progress = Progress::new(file_size);
+
+    let mut file = ParFile::with_config(filename.to_owned(), par_file_config);
+    let mut buf: Vec<u8> = Vec::new();
+
+    let result = index_list
+        .iter()
+        .map(|page_index| {
+            i += 1;
+
+            if i % 1_000 == 0 {
+                let event = progress.event(page_index.start_offset, i, SystemTime::now());
+                progress_reporter.report(&event);
+            }
+
+            read_file_slice(
+                &mut file,
+                page_index.start_offset,
+                page_index.end_offset.unwrap_or(file_size),
+                &mut buf,
+            );
+
+            (page_index, buf.clone())
+        })
+        .par_bridge()
+        .fold(&identity, |acc, (page_index, buf)| {
+            let page: P = serde_json::from_slice(&buf).unwrap();
+            fold(acc, page_index, page)
+        })
+        .reduce(&identity, reduce);
+
+    let event = progress.event(file_size, i, SystemTime::now());
+    progress_reporter.finish(&event);
+    println!();
+
+    result
+}
+
+pub fn read_file_slice(file: &mut impl Read, start: u64, end: u64, buf: &mut Vec<u8>) {
+    // file.seek(SeekFrom::Start(start)).unwrap();
+    buf.resize((end.checked_sub(start).expect("start > end")) as _, 0u8);
+    file.read_exact(buf).unwrap();
+}
+
+pub fn read_page<P: DeserializeOwned>(
+    file: &mut File,
+    start: u64,
+    end: Option<u64>,
+    buf: &mut Vec<u8>,
+) -> P {
+    file.seek(SeekFrom::Start(start)).unwrap();
+    let end = end.unwrap_or_else(|| file.metadata().unwrap().len());
+    buf.resize((end.checked_sub(start).expect("start > end")) as _, 0u8);
+    file.read_exact(buf).unwrap();
+
+    serde_json::from_slice(buf).unwrap()
+}
+
+pub fn write_lines<'a>(items: impl Iterator<Item = &'a str>, filename: &str) {
+    let mut out_str = String::new();
+    for item in items {
+        out_str.push_str(item);
+        out_str.push('\n');
+    }
+
+    let output_path = Path::new(filename);
+    if let Some(parent) = output_path.parent() {
+        std::fs::create_dir_all(parent).unwrap();
+    }
+    std::fs::write(filename, out_str).unwrap();
+}
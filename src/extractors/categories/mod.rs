@@ -0,0 +1,67 @@
+//! Categories a page belongs to, from its `[[Category:Foo|sort key]]` links.
+//!
+//! For pages in the Category namespace these are the parent categories, so the output of this
+//! extractor doubles as the category hierarchy. See [CategoryGraph](crate::category_graph).
+
+use serde::{Deserialize, Serialize};
+
+use super::links::{extract_links, LinkKind};
+use crate::wiki::{normalize_title, split_prefix, NAMESPACES};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Deserialize, Serialize)]
+pub struct Page {
+    pub title: String,
+    pub categories: Vec<Category>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct Category {
+    /// Category name without the `Category:` prefix
+    pub name: String,
+    /// Key the page is sorted by in the category listing, if it's not the page title
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sort_key: Option<String>,
+}
+
+pub fn extract(input: &[u8], title: &str) -> String {
+    let page = Page {
+        title: title.to_owned(),
+        categories: extract_categories(input),
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+pub fn extract_categories(input: &[u8]) -> Vec<Category> {
+    let mut categories: Vec<Category> = Vec::new();
+
+    for link in extract_links(input) {
+        if link.kind != LinkKind::Category {
+            continue;
+        }
+        let name = match split_prefix(&link.target, &NAMESPACES) {
+            Some((_, name)) => normalize_title(name),
+            None => continue,
+        };
+        // The label is the target itself when there's no sort key
+        let sort_key = Some(link.label)
+            .filter(|label| *label != link.target)
+            .map(|label| html_escape::decode_html_entities(&label).into_owned());
+
+        if !name.is_empty() && !categories.iter().any(|c| c.name == name) {
+            categories.push(Category { name, sort_key });
+        }
+    }
+
+    categories
+}
+
+/// Name of the category a title like `Category:Foo` is the page of
+pub fn category_name(title: &str) -> Option<&str> {
+    match split_prefix(title, &NAMESPACES) {
+        Some(("Category", name)) => Some(name.trim()),
+        _ => None,
+    }
+}
@@ -0,0 +1,41 @@
+use super::{category_name, extract_categories, Category};
+
+#[test]
+fn categories_and_sort_keys() {
+    let input = b"Text [[Paris]] [[Category:Capitals in Europe]]\n\
+        [[category:Cities_in France|Paris]] [[Category:Capitals in Europe|Duplicate]]\n\
+        [[Category:Anthropology| ]] [[:Category:Not a member]] &lt;!-- [[Category:Hidden]] --&gt;";
+
+    let category = |name: &str, sort_key: Option<&str>| Category {
+        name: name.to_owned(),
+        sort_key: sort_key.map(str::to_owned),
+    };
+    assert_eq!(
+        extract_categories(input),
+        vec![
+            category("Capitals in Europe", None),
+            category("Cities in France", Some("Paris")),
+            category("Anthropology", Some(" ")),
+        ]
+    );
+}
+
+#[test]
+fn raw_article() {
+    let input = std::fs::read("resources/test/raw_articles/Anthropology.txt").unwrap();
+    let names: Vec<_> = extract_categories(&input)
+        .into_iter()
+        .map(|category| category.name)
+        .collect();
+    assert_eq!(
+        names,
+        vec!["Anthropology", "Behavioural sciences", "Humans"]
+    );
+}
+
+#[test]
+fn category_names() {
+    assert_eq!(category_name("Category:Humans"), Some("Humans"));
+    assert_eq!(category_name("Humans"), None);
+    assert_eq!(category_name("Template:Humans"), None);
+}
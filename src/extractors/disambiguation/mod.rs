@@ -0,0 +1,145 @@
+//! Disambiguation pages and the articles they list, e.g. from `Mercury (disambiguation)`:
+//!
+//! ```text
+//! * [[Mercury (planet)]], the closest planet to the Sun
+//! * [[Mercury (element)|Mercury]] (Hg), a chemical element
+//! ```
+//!
+//! Each entry becomes a candidate with its one-line description, which is the candidate set an
+//! entity disambiguation model needs for the ambiguous term.
+
+use serde::{Deserialize, Serialize};
+
+use super::links::{extract_links_with_ends, LinkKind};
+use super::sections::Sections;
+use super::wikitext::fallback::strip_markup;
+use crate::wiki::normalize_title;
+
+#[cfg(test)]
+mod tests;
+
+/// Templates that mark a page as a disambiguation page
+const DISAMBIGUATION_TEMPLATES: &[&str] = &[
+    "disambiguation",
+    "disambig",
+    "disamb",
+    "dab",
+    "hndis",
+    "geodis",
+    "numberdis",
+    "letter disambiguation",
+    "species latin name disambiguation",
+];
+
+#[derive(Deserialize, Serialize)]
+pub struct Page {
+    pub title: String,
+    pub candidates: Vec<Candidate>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct Candidate {
+    pub target: String,
+    pub description: String,
+    /// Heading the entry is listed under, like `Science` or `People`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub section: Option<String>,
+}
+
+pub fn extract(input: &[u8], title: &str) -> String {
+    let page = Page {
+        title: title.to_owned(),
+        candidates: extract_candidates(input),
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+/// Whether the title is that of a disambiguation page, like `Mercury (disambiguation)`
+pub fn is_disambiguation_title(title: &str) -> bool {
+    let suffix = b"(disambiguation)";
+    title.len() >= suffix.len()
+        && title.as_bytes()[title.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+}
+
+/// Whether the page is marked as a disambiguation page by one of its templates. This runs on the
+/// thread reading the dump for every page, so template names are compared in place.
+pub fn is_disambiguation(input: &[u8]) -> bool {
+    let mut i = 0;
+    while let Some(offset) = input[i..].iter().position(|&b| b == b'{') {
+        i += offset + 1;
+        if input.get(i) != Some(&b'{') {
+            continue;
+        }
+        i += 1;
+
+        let rest = &input[i..];
+        let name_end = rest
+            .iter()
+            .position(|&b| b == b'|' || b == b'}' || b == b'\n')
+            .unwrap_or(rest.len());
+        let name = rest[..name_end].trim_ascii();
+        if DISAMBIGUATION_TEMPLATES
+            .iter()
+            .any(|template| is_template_name(name, template))
+        {
+            return true;
+        }
+    }
+    false
+}
+
+// Compare a template name from the wikitext to a lowercase one, ignoring case and treating
+// underscores as spaces
+fn is_template_name(name: &[u8], template: &str) -> bool {
+    name.len() == template.len()
+        && name
+            .iter()
+            .zip(template.bytes())
+            .all(|(&a, b)| a.to_ascii_lowercase() == b || (a == b'_' && b == b' '))
+}
+
+/// The list entries of a disambiguation page: the first article link of every `*` line and the
+/// text after it
+pub fn extract_candidates(input: &[u8]) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+    let mut sections = Sections::new();
+    let mut section = None;
+
+    for line in input.split(|&b| b == b'\n') {
+        let line = line.trim_ascii();
+
+        if line.starts_with(b"==") && sections.update(&String::from_utf8_lossy(line)) {
+            section = sections.current().map(|name| strip_markup(name.as_bytes()));
+            continue;
+        }
+        if !line.starts_with(b"*") {
+            continue;
+        }
+
+        let (link_end, link) = match extract_links_with_ends(line)
+            .into_iter()
+            .find(|(_, link)| link.kind == LinkKind::Article)
+        {
+            Some(found) => found,
+            None => continue,
+        };
+        let target = normalize_title(&link.target);
+        if target.is_empty() || candidates.iter().any(|c| c.target == target) {
+            continue;
+        }
+
+        let description = strip_markup(&line[link_end..]);
+        let description = description
+            .trim_start_matches(|c: char| c == ',' || c == '\'' || c.is_whitespace())
+            .trim()
+            .to_owned();
+
+        candidates.push(Candidate {
+            target,
+            description,
+            section: section.clone(),
+        });
+    }
+
+    candidates
+}
@@ -0,0 +1,80 @@
+use super::{extract_candidates, is_disambiguation, is_disambiguation_title, Candidate};
+
+const MERCURY: &[u8] = b"'''Mercury''' commonly refers to:\n\
+* [[Mercury (planet)]], the closest planet to the Sun\n\
+* [[Mercury (element)|Mercury]] (Hg), a chemical element\n\
+\n\
+'''Mercury''' may also refer to:\n\
+\n\
+== Film ==\n\
+* ''[[Mercury (film)|Mercury]]'', a 1998 film by [[Someone]]\n\
+\n\
+== People ==\n\
+* [[Freddie Mercury]] (1946&ndash;1991), British singer\n\
+* [[mercury (planet)|Duplicate]]\n\
+\n\
+== See also ==\n\
+* {{lookfrom|Mercury}}\n\
+* [[Category:Planets]]\n\
+\n\
+{{Disambiguation|geo}}\n";
+
+#[test]
+fn detection() {
+    assert!(is_disambiguation(MERCURY));
+    assert!(is_disambiguation(b"text {{ dab }}"));
+    assert!(is_disambiguation(b"text {{Hndis|name=Smith, John}}"));
+    assert!(!is_disambiguation(
+        b"{{Distinguish|Mercury}} [[Mercury (planet)]]"
+    ));
+    assert!(!is_disambiguation(b"{{Disambiguation needed}}"));
+    assert!(is_disambiguation(b"{{Letter_Disambiguation}}"));
+    assert!(!is_disambiguation(b"{{{dab}}} {dab} {{"));
+
+    assert!(is_disambiguation_title("Mercury (disambiguation)"));
+    assert!(is_disambiguation_title("Mercury (Disambiguation)"));
+    assert!(!is_disambiguation_title("Mercury"));
+    assert!(!is_disambiguation_title("Ü"));
+}
+
+#[test]
+fn candidates() {
+    let candidate = |target: &str, description: &str, section: Option<&str>| Candidate {
+        target: target.to_owned(),
+        description: description.to_owned(),
+        section: section.map(str::to_owned),
+    };
+    assert_eq!(
+        extract_candidates(MERCURY),
+        vec![
+            candidate("Mercury (planet)", "the closest planet to the Sun", None),
+            candidate("Mercury (element)", "(Hg), a chemical element", None),
+            candidate("Mercury (film)", "a 1998 film by Someone", Some("Film")),
+            candidate(
+                "Freddie Mercury",
+                "(1946–1991), British singer",
+                Some("People")
+            ),
+        ]
+    );
+}
+
+#[test]
+fn description_follows_the_candidate_link() {
+    let input = b"* [[File:Hg.svg|20px]] [[Mercury (element)]], a [[chemical element]]\n\
+        * [[wikt:mercury|mercury]] in [[Mercury (mythology)|Roman mythology]], a god";
+    let candidates: Vec<_> = extract_candidates(input)
+        .into_iter()
+        .map(|c| (c.target, c.description))
+        .collect();
+    assert_eq!(
+        candidates,
+        vec![
+            (
+                "Mercury (element)".to_owned(),
+                "a chemical element".to_owned()
+            ),
+            ("Mercury (mythology)".to_owned(), "a god".to_owned()),
+        ]
+    );
+}
@@ -0,0 +1,262 @@
+//! Links to pages outside of Wikipedia: `[https://example.org label]`, bare URLs, and the `url=`
+//! parameter of citation templates like `{{cite web}}`.
+
+use html_escape::decode_html_entities;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::sections::Sections;
+
+#[cfg(test)]
+mod tests;
+
+lazy_static! {
+    static ref BRACKETED: Regex =
+        Regex::new(r"\[((?:https?:|ftp:)?//[^\s\[\]]+)(?:[ \t]+([^\]\n]*))?\]").unwrap();
+    static ref BARE: Regex = Regex::new(r#"(?:https?|ftp)://[^\s\[\]<>"{}|]+"#).unwrap();
+    static ref COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Page {
+    pub title: String,
+    pub links: Vec<ExternalLink>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct ExternalLink {
+    pub url: String,
+    /// Host name of the url, lowercased and without `www.`
+    pub domain: String,
+    pub label: Option<String>,
+    pub source: Source,
+    /// Name of the citation template, for links found in citations
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub template: Option<String>,
+    /// Names of the enclosing sections, starting with the article title
+    pub section: Vec<String>,
+}
+
+/// Where in the wikitext an external link was found
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    /// `[https://example.org label]`
+    Bracketed,
+    /// A URL in running text
+    Bare,
+    /// The `url=` parameter of a citation template
+    Citation,
+}
+
+pub fn extract(input: &[u8], title: &str) -> String {
+    let page = Page {
+        title: title.to_owned(),
+        links: extract_external_links(input, title),
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+pub fn extract_external_links(input: &[u8], title: &str) -> Vec<ExternalLink> {
+    let input = String::from_utf8_lossy(input);
+    let input = decode_html_entities(&input);
+
+    // Blank out comments, keeping offsets intact
+    let input = COMMENT.replace_all(&input, |caps: &regex::Captures| " ".repeat(caps[0].len()));
+
+    let sections = SectionStarts::new(title, &input);
+    let mut links = Vec::new();
+    // Byte ranges already accounted for, so a URL isn't also picked up as a bare URL
+    let mut covered = Vec::new();
+
+    for (start, end, name) in citation_templates(&input) {
+        let template = &input[start..end];
+        let params = template_params(&template[2..template.len() - 2]);
+        let param = |key: &str| {
+            params
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.trim())
+                .filter(|v| !v.is_empty())
+        };
+
+        if let Some(url) = param("url") {
+            links.push(ExternalLink {
+                url: url.to_owned(),
+                domain: domain(url),
+                label: param("title").map(str::to_owned),
+                source: Source::Citation,
+                template: Some(name),
+                section: sections.at(start),
+            });
+        }
+        covered.push((start, end));
+    }
+
+    for caps in BRACKETED.captures_iter(&input) {
+        let whole = caps.get(0).unwrap();
+        // [[...]] is an internal link
+        if input[..whole.start()].ends_with('[') || is_covered(&covered, whole.start()) {
+            continue;
+        }
+
+        let url = &caps[1];
+        links.push(ExternalLink {
+            url: url.to_owned(),
+            domain: domain(url),
+            label: caps.get(2).map(|m| m.as_str().trim().to_owned()),
+            source: Source::Bracketed,
+            template: None,
+            section: sections.at(whole.start()),
+        });
+        covered.push((whole.start(), whole.end()));
+    }
+
+    for m in BARE.find_iter(&input) {
+        if is_covered(&covered, m.start()) {
+            continue;
+        }
+
+        let url = m.as_str().trim_end_matches(|c| ".,;:!?)'".contains(c));
+        links.push(ExternalLink {
+            url: url.to_owned(),
+            domain: domain(url),
+            label: None,
+            source: Source::Bare,
+            template: None,
+            section: sections.at(m.start()),
+        });
+    }
+
+    links
+}
+
+fn is_covered(covered: &[(usize, usize)], pos: usize) -> bool {
+    covered
+        .iter()
+        .any(|&(start, end)| start <= pos && pos < end)
+}
+
+/// Host part of a URL, lowercased and without `www.`
+pub fn domain(url: &str) -> String {
+    let rest = match url.split_once("//") {
+        Some((_, rest)) => rest,
+        None => url,
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    // Drop user info and port
+    let host = host.rsplit('@').next().unwrap_or(host);
+    let host = host.split(':').next().unwrap_or(host);
+    let host = host.to_lowercase();
+    host.strip_prefix("www.").unwrap_or(&host).to_owned()
+}
+
+// Find `{{cite ...}}` and `{{citation ...}}` templates, returning (start, end, template name)
+fn citation_templates(input: &str) -> Vec<(usize, usize, String)> {
+    let bytes = input.as_bytes();
+    let mut templates = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = input[pos..].find("{{") {
+        let start = pos + offset;
+        let name_end = input[start + 2..]
+            .find(['|', '}'])
+            .map_or(input.len(), |i| start + 2 + i);
+        let name = input[start + 2..name_end].trim().to_lowercase();
+
+        if !(name.starts_with("cite") || name == "citation") {
+            pos = start + 2;
+            continue;
+        }
+
+        // Find the matching }}
+        let mut depth = 0;
+        let mut i = start;
+        let mut end = None;
+        while i + 1 < bytes.len() {
+            if bytes[i] == b'{' && bytes[i + 1] == b'{' {
+                depth += 1;
+                i += 2;
+            } else if bytes[i] == b'}' && bytes[i + 1] == b'}' {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+            } else {
+                i += 1;
+            }
+        }
+
+        let Some(end) = end else {
+            // Unterminated template. Citations after it, including ones nested in it, are still
+            // complete.
+            pos = start + 2;
+            continue;
+        };
+
+        templates.push((start, end, name.replace('_', " ")));
+        pos = end;
+    }
+
+    templates
+}
+
+// Split the inside of a template at top level pipes and return its named parameters
+fn template_params(inner: &str) -> Vec<(&str, &str)> {
+    let mut params = Vec::new();
+    let mut depth = 0i32;
+    let mut param_start = 0;
+    let bytes = inner.as_bytes();
+
+    for (i, &b) in bytes.iter().enumerate() {
+        match b {
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => depth -= 1,
+            b'|' if depth == 0 => {
+                if let Some((k, v)) = inner[param_start..i].split_once('=') {
+                    params.push((k.trim(), v));
+                }
+                param_start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    if let Some((k, v)) = inner[param_start..].split_once('=') {
+        params.push((k.trim(), v));
+    }
+
+    params
+}
+
+// Section path at every header, so the section of any offset can be looked up
+struct SectionStarts {
+    // (offset of the header, section path from that offset on)
+    starts: Vec<(usize, Vec<String>)>,
+}
+
+impl SectionStarts {
+    fn new(title: &str, input: &str) -> Self {
+        let mut starts = vec![(0, vec![title.to_owned()])];
+        let mut sections = Sections::new();
+
+        let mut offset = 0;
+        for line in input.split_inclusive('\n') {
+            if sections.update(line) {
+                let mut path = vec![title.to_owned()];
+                path.extend(sections.path().map(str::to_owned));
+                starts.push((offset, path));
+            }
+            offset += line.len();
+        }
+
+        Self { starts }
+    }
+
+    fn at(&self, offset: usize) -> Vec<String> {
+        let i = self.starts.partition_point(|(start, _)| *start <= offset);
+        self.starts[i - 1].1.clone()
+    }
+}
@@ -0,0 +1,103 @@
+use super::{domain, extract_external_links, ExternalLink, Source};
+
+#[test]
+fn domains() {
+    let cases = [
+        ("https://www.example.org/page?q=1", "example.org"),
+        ("http://News.BBC.co.uk/2/hi", "news.bbc.co.uk"),
+        ("//example.org#top", "example.org"),
+        ("ftp://user@files.example.org:21/a", "files.example.org"),
+    ];
+    for (url, expected) in cases {
+        assert_eq!(domain(url), expected, "{}", url);
+    }
+}
+
+#[test]
+fn bracketed_bare_and_citations() {
+    let input = b"Intro [https://www.example.org/a Example site] and [[Paris]].\n\
+        == History ==\n\
+        See http://bare.example.com/x, too.\n\
+        === Early ===\n\
+        Text.<ref>{{cite web |url=https://news.example.net/story |title=A {{lang|fr|story}} |access-date=2020}}</ref>\n\
+        <!-- http://hidden.example.com -->\n\
+        [http://unlabeled.example.com]\n";
+
+    let links = extract_external_links(input, "Page");
+    let section = |path: &[&str]| path.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+    assert_eq!(
+        links,
+        vec![
+            ExternalLink {
+                url: "https://news.example.net/story".to_owned(),
+                domain: "news.example.net".to_owned(),
+                label: Some("A {{lang|fr|story}}".to_owned()),
+                source: Source::Citation,
+                template: Some("cite web".to_owned()),
+                section: section(&["Page", "History", "Early"]),
+            },
+            ExternalLink {
+                url: "https://www.example.org/a".to_owned(),
+                domain: "example.org".to_owned(),
+                label: Some("Example site".to_owned()),
+                source: Source::Bracketed,
+                template: None,
+                section: section(&["Page"]),
+            },
+            ExternalLink {
+                url: "http://unlabeled.example.com".to_owned(),
+                domain: "unlabeled.example.com".to_owned(),
+                label: None,
+                source: Source::Bracketed,
+                template: None,
+                section: section(&["Page", "History", "Early"]),
+            },
+            ExternalLink {
+                url: "http://bare.example.com/x".to_owned(),
+                domain: "bare.example.com".to_owned(),
+                label: None,
+                source: Source::Bare,
+                template: None,
+                section: section(&["Page", "History"]),
+            },
+        ]
+    );
+}
+
+#[test]
+fn escaped_input() {
+    let input =
+        b"[https://example.org/?a=1&amp;b=2 Label] &lt;!-- https://hidden.example.org --&gt;";
+    let links = extract_external_links(input, "Page");
+
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].url, "https://example.org/?a=1&b=2");
+}
+
+#[test]
+fn citation_after_unterminated_template() {
+    let input = b"{{cite web |url=https://broken.example.org |title=Broken\n\
+        Text.<ref>{{cite web |url=https://news.example.net/story |title=Story}}</ref>\n\
+        {{citation |url=https://book.example.com}}";
+    let links = extract_external_links(input, "Page");
+
+    let citations: Vec<_> = links
+        .iter()
+        .filter(|link| link.source == Source::Citation)
+        .map(|link| link.url.as_str())
+        .collect();
+    assert_eq!(
+        citations,
+        vec!["https://news.example.net/story", "https://book.example.com"]
+    );
+}
+
+#[test]
+fn unbalanced_headers() {
+    let input = "==Café=\nhttps://cafe.example.org\n===Crème brûlée==\nhttps://creme.example.org";
+    let links = extract_external_links(input.as_bytes(), "Page");
+
+    let sections: Vec<_> = links.iter().map(|link| link.section.join(" > ")).collect();
+    assert_eq!(sections, vec!["Page > Café", "Page > Café > Crème brûlée"]);
+}
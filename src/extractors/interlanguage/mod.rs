@@ -0,0 +1,55 @@
+//! Interlanguage links like `[[de:Paris]]`, which tie an article to its versions in other
+//! languages.
+
+use serde::{Deserialize, Serialize};
+
+use super::links::{extract_links, LinkKind};
+use crate::wiki::{normalize_title, split_project_prefix, LANGUAGE_CODES};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Deserialize, Serialize)]
+pub struct Page {
+    pub title: String,
+    pub links: Vec<InterlanguageLink>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct InterlanguageLink {
+    /// Language code, like `de` or `zh-yue`
+    pub language: String,
+    /// Title of the article in that language
+    pub title: String,
+}
+
+pub fn extract(input: &[u8], title: &str) -> String {
+    let page = Page {
+        title: title.to_owned(),
+        links: extract_interlanguage_links(input),
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+pub fn extract_interlanguage_links(input: &[u8]) -> Vec<InterlanguageLink> {
+    let mut links: Vec<InterlanguageLink> = Vec::new();
+
+    for link in extract_links(input) {
+        if link.kind != LinkKind::Interlanguage {
+            continue;
+        }
+        let (language, title) = match split_project_prefix(&link.target, LANGUAGE_CODES) {
+            Some(x) => x,
+            None => continue,
+        };
+        let link = InterlanguageLink {
+            language: language.to_owned(),
+            title: normalize_title(title),
+        };
+        if !link.title.is_empty() && !links.contains(&link) {
+            links.push(link);
+        }
+    }
+
+    links
+}
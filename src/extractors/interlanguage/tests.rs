@@ -0,0 +1,20 @@
+use super::{extract_interlanguage_links, InterlanguageLink};
+
+#[test]
+fn interlanguage_links() {
+    let input = b"[[Paris]] [[de:Paris]] [[:fr:Paris]] [[fr:paris_(ville)]] [[zh-yue:\xe5\xb7\xb4\xe9\xbb\x8e]]\n\
+        [[de:Paris]] [[wikt:Paris]] [[Category:Cities]] [[IT: Information technology]] [[en:Paris]]";
+
+    let link = |language: &str, title: &str| InterlanguageLink {
+        language: language.to_owned(),
+        title: title.to_owned(),
+    };
+    assert_eq!(
+        extract_interlanguage_links(input),
+        vec![
+            link("de", "Paris"),
+            link("fr", "Paris (ville)"),
+            link("zh-yue", "巴黎"),
+        ]
+    );
+}
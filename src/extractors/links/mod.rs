@@ -0,0 +1,566 @@
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use memchr::memmem;
+use serde::{Deserialize, Serialize};
+
+use crate::page_guard::check_deadline;
+use crate::wiki::{
+    self, split_prefix, split_project_prefix, INTERWIKI_PREFIXES, LANGUAGE_CODES, NAMESPACES,
+    NAMESPACE_ALIASES,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Deserialize, Serialize)]
+pub struct Page {
+    pub title: String,
+    pub links: Vec<Link>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Link {
+    pub target: String,
+    pub label: String,
+    // Older outputs don't have this field, and only contain article links in practice
+    #[serde(default)]
+    pub kind: LinkKind,
+    /// Name of the innermost template the link appears in, or that generated it
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub template: Option<String>,
+    /// Template parameter the link appears in. Positional parameters are numbered from 1.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub parameter: Option<String>,
+}
+
+/// What a link points at, derived from the prefix of its target
+#[derive(Deserialize, Serialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    /// An article in the main namespace
+    #[default]
+    Article,
+    /// `[[Category:Foo]]`, which puts the page in a category
+    Category,
+    /// `[[File:Foo.jpg]]`, which embeds a file
+    File,
+    /// A link to a page in any other namespace, including `[[:Category:Foo]]` and `[[:File:Foo]]`
+    Namespace,
+    /// A link to another wiki, like `[[wikt:foo]]` or `[[:fr:Paris]]`
+    Interwiki,
+    /// `[[fr:Paris]]`, which links the page to its version in another language
+    Interlanguage,
+}
+
+impl LinkKind {
+    /// Kind of a link to `target` on the wiki being processed, see [wiki::language]
+    pub fn classify(target: &str) -> Self {
+        Self::classify_in(target, wiki::language())
+    }
+
+    /// Kind of a link to `target` on the wiki in `language`
+    pub fn classify_in(target: &str, language: &str) -> Self {
+        // A leading colon turns categories, files and interlanguage links into plain links
+        let (colon, target) = match target.trim_start().strip_prefix(':') {
+            Some(rest) => (true, rest),
+            None => (false, target.trim_start()),
+        };
+
+        if let Some((namespace, _)) = split_prefix(target, &NAMESPACES) {
+            return match (namespace, colon) {
+                ("Category", false) => LinkKind::Category,
+                ("File" | "Media", false) => LinkKind::File,
+                _ => LinkKind::Namespace,
+            };
+        }
+        if let Some((alias, _)) = split_prefix(target, &NAMESPACE_ALIASES) {
+            return match (alias, colon) {
+                ("Image", false) => LinkKind::File,
+                _ => LinkKind::Namespace,
+            };
+        }
+        if let Some((code, rest)) = split_project_prefix(target, LANGUAGE_CODES) {
+            if code == language {
+                // A link to the wiki itself, so never a category or file to add to the page
+                return Self::classify_in(&format!(":{}", rest), language);
+            }
+            return if colon {
+                LinkKind::Interwiki
+            } else {
+                LinkKind::Interlanguage
+            };
+        }
+        if split_project_prefix(target, &INTERWIKI_PREFIXES).is_some() {
+            return LinkKind::Interwiki;
+        }
+
+        LinkKind::Article
+    }
+
+    /// Kinds in `include` (all kinds if empty) that are not in `exclude`
+    pub fn selection(include: &[LinkKind], exclude: &[LinkKind]) -> Vec<LinkKind> {
+        let all = LinkKind::value_variants();
+        let include = if include.is_empty() { all } else { include };
+        include
+            .iter()
+            .filter(|kind| !exclude.contains(kind))
+            .copied()
+            .collect()
+    }
+}
+
+pub fn extract(input: &[u8], title: &str) -> String {
+    extract_kinds(input, title, LinkKind::value_variants())
+}
+
+/// Same as [extract], keeping only links of the given kinds
+pub fn extract_kinds(input: &[u8], title: &str, kinds: &[LinkKind]) -> String {
+    let mut links = extract_links(input);
+    links.retain(|link| kinds.contains(&link.kind));
+    let page = Page {
+        title: title.to_owned(),
+        links,
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+/// Templates whose positional parameters are link targets, like `{{main|Foo|Bar}}`
+const HATNOTE_TEMPLATES: &[&str] = &[
+    "main",
+    "main article",
+    "see also",
+    "seealso",
+    "further",
+    "further information",
+    "details",
+];
+
+/// Interlanguage link templates, `{{ill|Target|fr|Cible|lt=label}}`, which link to their first
+/// parameter
+const ILL_TEMPLATES: &[&str] = &["ill", "interlanguage link", "interlanguage link multi"];
+
+// A template that is open at the current position of the scanner
+struct Template {
+    name: Option<String>,
+    // Start of the parameter currently being read
+    param_start: usize,
+    // Number of positional parameters read so far
+    positional: usize,
+    // (name, value) of the parameters read so far
+    params: Vec<(String, String)>,
+    // Positions in the output of the links tagged with this template
+    tagged: Vec<usize>,
+}
+
+impl Template {
+    fn new(param_start: usize) -> Self {
+        Self {
+            name: None,
+            param_start,
+            positional: 0,
+            params: Vec::new(),
+            tagged: Vec::new(),
+        }
+    }
+
+    // Name of the parameter that `text` starts, numbering positional ones from 1
+    fn param_name(&self, text: &[u8]) -> String {
+        match named_param(text) {
+            Some((name, _)) => name,
+            None => (self.positional + 1).to_string(),
+        }
+    }
+
+    // Finish the parameter ending at `end`
+    fn end_param(&mut self, input: &[u8], end: usize) {
+        let text = &input[self.param_start..end];
+        if self.name.is_none() {
+            let name = String::from_utf8_lossy(text);
+            self.name = Some(name.trim().replace('_', " "));
+            return;
+        }
+
+        let param = match named_param(text) {
+            Some((name, value)) => (name, value),
+            None => {
+                self.positional += 1;
+                let value = String::from_utf8_lossy(text).trim().to_owned();
+                (self.positional.to_string(), value)
+            }
+        };
+        self.params.push(param);
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+            .filter(|value| !value.is_empty())
+    }
+
+    // Links a hatnote or interlanguage link template stands for
+    fn links(&self) -> Vec<Link> {
+        let name = match &self.name {
+            Some(name) => name.to_lowercase(),
+            None => return Vec::new(),
+        };
+
+        let targets: Vec<_> = if HATNOTE_TEMPLATES.contains(&name.as_str()) {
+            (1..=self.positional)
+                .filter_map(|i| {
+                    let target = self.param(&i.to_string())?;
+                    let label = self.param(&format!("l{}", i)).unwrap_or(target);
+                    Some((i, target, label))
+                })
+                .collect()
+        } else if ILL_TEMPLATES.contains(&name.as_str()) {
+            self.param("1")
+                .map(|target| (1, target, self.param("lt").unwrap_or(target)))
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        targets
+            .into_iter()
+            .map(|(i, target, label)| Link {
+                target: target.to_owned(),
+                label: label.to_owned(),
+                kind: LinkKind::classify(target),
+                template: self.name.clone(),
+                parameter: Some(i.to_string()),
+            })
+            .collect()
+    }
+}
+
+// Split `name = value` if the text before the first `=` is a plain parameter name
+fn named_param(text: &[u8]) -> Option<(String, String)> {
+    let eq = text.iter().position(|&b| b == b'=')?;
+    let name = &text[..eq];
+    if name.iter().any(|&b| b == b'[' || b == b'{' || b == b'\n') {
+        return None;
+    }
+
+    let name = String::from_utf8_lossy(name).trim().to_owned();
+    let value = String::from_utf8_lossy(&text[eq + 1..]).trim().to_owned();
+    Some((name, value))
+}
+
+/// Tags whose contents are not parsed as wikitext
+const UNPARSED_TAGS: &[&[u8]] = &[b"nowiki", b"pre", b"math", b"syntaxhighlight", b"source"];
+
+// We operate on u8 instead of chars to avoid the overhead of decoding UTF8.
+// This works because UTF8 guarantees that the multibyte UTF8 sequences won't contain any ASCII
+// characters. See the Backwards Compatibility section here:
+// https://en.wikipedia.org/wiki/UTF-8#Comparison_with_other_encodings
+//
+// The input may or may not have its XML entities decoded, so tags are recognized both as `<tag>`
+// and `&lt;tag&gt;`.
+pub(crate) fn extract_links(input: &[u8]) -> Vec<Link> {
+    extract_links_with_ends(input)
+        .into_iter()
+        .map(|(_, link)| link)
+        .collect()
+}
+
+/// Same as [extract_links], along with the position just after each link: after its closing
+/// brackets, or after the closing braces of the template it stands for
+pub(crate) fn extract_links_with_ends(input: &[u8]) -> Vec<(usize, Link)> {
+    let mut links = Vec::new();
+    // Templates enclosing the current position, innermost last
+    let mut templates: Vec<Template> = Vec::new();
+    // Starts of opening brackets that turned out not to be links
+    let mut invalid = HashSet::new();
+
+    let mut i = 0;
+    while i + 1 < input.len() {
+        check_deadline();
+        let pair = (input[i], input[i + 1]);
+
+        if let Some((end, _)) = skip_unparsed(input, i) {
+            i = end;
+        } else if pair == (b'{', b'{') {
+            templates.push(Template::new(i + 2));
+            i += 2;
+        } else if pair == (b'}', b'}') && !templates.is_empty() {
+            let mut template = templates.pop().unwrap();
+            template.end_param(input, i);
+            i += 2;
+            links.extend(template.links().into_iter().map(|link| (i, link)));
+        } else if input[i] == b'|' && !templates.is_empty() {
+            templates.last_mut().unwrap().end_param(input, i);
+            templates.last_mut().unwrap().param_start = i + 1;
+            i += 1;
+        } else if pair == (b'[', b'[') {
+            match scan_link(input, i + 2, &mut invalid) {
+                Some((link_end, mut found)) => {
+                    if let Some(template) = templates.last_mut() {
+                        let parameter = template
+                            .name
+                            .as_ref()
+                            .map(|_| template.param_name(&input[template.param_start..i]));
+                        for (_, link) in &mut found {
+                            link.template = template.name.clone();
+                            link.parameter = parameter.clone();
+                        }
+                        template
+                            .tagged
+                            .extend(links.len()..links.len() + found.len());
+                    }
+                    links.extend(found);
+                    i = link_end;
+                }
+                // Not a link. The second bracket may still open one, as in `[[[Target]]]`.
+                None => i += 1,
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    // Templates that are never closed are plain text, so the links in them are in the body
+    for template in templates {
+        for position in template.tagged {
+            let (_, link) = &mut links[position];
+            link.template = None;
+            link.parameter = None;
+        }
+    }
+
+    links
+}
+
+// A link that is open at the current position of [scan_link]
+struct OpenLink {
+    // Position just after the opening brackets
+    start: usize,
+    target: Vec<u8>,
+    label: Vec<u8>,
+    has_label: bool,
+    nested_braces: usize,
+    // Links found in the label so far, with their ends
+    nested: Vec<(usize, Link)>,
+}
+
+impl OpenLink {
+    fn new(start: usize) -> Self {
+        Self {
+            start,
+            target: Vec::new(),
+            label: Vec::new(),
+            has_label: false,
+            nested_braces: 0,
+            nested: Vec::new(),
+        }
+    }
+
+    fn buffer(&mut self) -> &mut Vec<u8> {
+        if self.has_label {
+            &mut self.label
+        } else {
+            &mut self.target
+        }
+    }
+
+    // The link ending at `end` followed by the links nested in its label, with their ends
+    fn finish(self, end: usize) -> Vec<(usize, Link)> {
+        let target = String::from_utf8_lossy(&self.target).to_string();
+        let label = if self.has_label {
+            String::from_utf8_lossy(&self.label).to_string()
+        } else {
+            target.clone()
+        };
+        let kind = LinkKind::classify(&target);
+        let link = Link {
+            label,
+            target,
+            kind,
+            template: None,
+            parameter: None,
+        };
+
+        let mut links = vec![(end, link)];
+        links.extend(self.nested);
+        links
+    }
+}
+
+// Read the link starting at `start`, just after the opening brackets. Returns the position after
+// the closing brackets, and the link followed by any links nested in its label, like the ones in
+// a file caption, each with the position after it. Returns None if this isn't a valid link: the
+// target spans lines or contains brackets, or the link isn't closed before the end of the
+// paragraph.
+//
+// Nested links are kept on a stack rather than read recursively, and the starts of links found to
+// be invalid are remembered in `invalid`, so that no opening brackets are tried twice and
+// unclosed links like `[[a|[[a|[[a|...` take linear time.
+fn scan_link(
+    input: &[u8],
+    start: usize,
+    invalid: &mut HashSet<usize>,
+) -> Option<(usize, Vec<(usize, Link)>)> {
+    if invalid.contains(&start) {
+        return None;
+    }
+    let mut stack = vec![OpenLink::new(start)];
+
+    let mut i = start;
+    loop {
+        check_deadline();
+        if i + 1 >= input.len() {
+            invalid.extend(stack.iter().map(|open| open.start));
+            return None;
+        }
+        let b = input[i];
+        let open = stack.last_mut().unwrap();
+
+        if let Some((end, content)) = skip_unparsed(input, i) {
+            open.buffer().extend_from_slice(&input[content]);
+            i = end;
+            continue;
+        }
+
+        if b == b']' && input[i + 1] == b']' {
+            i += 2;
+            let found = stack.pop().unwrap().finish(i);
+            match stack.last_mut() {
+                Some(parent) => {
+                    // The nested link shows up as its label
+                    parent.label.extend_from_slice(found[0].1.label.as_bytes());
+                    parent.nested.extend(found);
+                    continue;
+                }
+                None => return Some((i, found)),
+            }
+        }
+
+        if !open.has_label {
+            match b {
+                b'|' if open.nested_braces == 0 => {
+                    open.has_label = true;
+                    i += 1;
+                    continue;
+                }
+                b'\n' | b'[' | b']' => {
+                    let failed = stack.pop().unwrap();
+                    invalid.insert(failed.start);
+                    if stack.is_empty() {
+                        return None;
+                    }
+                    // Not a link, read its brackets again as plain text of the enclosing label
+                    i = failed.start - 2;
+                    continue;
+                }
+                _ => (),
+            }
+        } else if b == b'\n' && input[i + 1] == b'\n' {
+            // The enclosing links can't be closed before the end of the paragraph either
+            invalid.extend(stack.iter().map(|open| open.start));
+            return None;
+        } else if b == b'[' && input[i + 1] == b'[' && !invalid.contains(&(i + 2)) {
+            stack.push(OpenLink::new(i + 2));
+            i += 2;
+            continue;
+        }
+
+        if b == b'{' {
+            open.nested_braces += 1;
+        } else if b == b'}' {
+            open.nested_braces = open.nested_braces.saturating_sub(1);
+        }
+
+        open.buffer().push(b);
+        i += 1;
+    }
+}
+
+// If a comment or an unparsed tag like `<nowiki>` starts at `i`, return the position after it and
+// the range of its literal contents. Comments have no contents, and an unclosed comment runs to
+// the end of the input. Unclosed tags are plain text.
+fn skip_unparsed(input: &[u8], i: usize) -> Option<(usize, std::ops::Range<usize>)> {
+    if input[i] != b'<' && input[i] != b'&' {
+        return None;
+    }
+    let rest = &input[i..];
+
+    // Only the closer written the same way can end a comment, so looking for the other one
+    // doesn't scan the rest of the page for every comment
+    for (open, close) in [(&b"<!--"[..], &b"-->"[..]), (b"&lt;!--", b"--&gt;")] {
+        if rest.starts_with(open) {
+            let end = find(input, i + open.len(), close).map_or(input.len(), |j| j + close.len());
+            return Some((end, end..end));
+        }
+    }
+
+    let name_start = if rest.starts_with(b"<") {
+        i + 1
+    } else if rest.starts_with(b"&lt;") {
+        i + 4
+    } else {
+        return None;
+    };
+    let tag = UNPARSED_TAGS
+        .iter()
+        .find(|tag| starts_with_ignore_case(&input[name_start..], tag))?;
+    // The name must end here, so that `<pre>` doesn't match `<prefix>`
+    let after_name = name_start + tag.len();
+    if !matches!(input.get(after_name), Some(b'>' | b'/' | b' ' | b'&')) {
+        return None;
+    }
+
+    let (open_end, self_closing) = tag_end(input, after_name)?;
+    if self_closing {
+        return Some((open_end, open_end..open_end));
+    }
+
+    // Find the closing tag
+    let mut j = open_end;
+    while j < input.len() {
+        let close_name = if input[j..].starts_with(b"</") {
+            j + 2
+        } else if input[j..].starts_with(b"&lt;/") {
+            j + 5
+        } else {
+            j += 1;
+            continue;
+        };
+        if starts_with_ignore_case(&input[close_name..], tag) {
+            let end = tag_end(input, close_name + tag.len()).map_or(input.len(), |(end, _)| end);
+            return Some((end, open_end..j));
+        }
+        j += 1;
+    }
+
+    None
+}
+
+// Position after the `>` or `&gt;` closing the tag that continues at `i`, and whether the tag is
+// self closing
+fn tag_end(input: &[u8], i: usize) -> Option<(usize, bool)> {
+    let mut j = i;
+    while j < input.len() {
+        if input[j] == b'>' {
+            return Some((j + 1, input[j - 1] == b'/'));
+        }
+        if input[j..].starts_with(b"&gt;") {
+            return Some((j + 4, input[j - 1] == b'/'));
+        }
+        if input[j] == b'<' || input[j] == b'\n' {
+            return None;
+        }
+        j += 1;
+    }
+    None
+}
+
+fn find(input: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    memmem::find(&input[from..], needle).map(|j| from + j)
+}
+
+fn starts_with_ignore_case(input: &[u8], prefix: &[u8]) -> bool {
+    input.len() >= prefix.len() && input[..prefix.len()].eq_ignore_ascii_case(prefix)
+}
@@ -0,0 +1,259 @@
+use super::{extract_links, LinkKind};
+
+#[test]
+fn classify() {
+    let cases = [
+        ("Paris", LinkKind::Article),
+        ("Star Wars: Episode IV", LinkKind::Article),
+        ("Category:Capitals in Europe", LinkKind::Category),
+        ("category:Capitals in Europe", LinkKind::Category),
+        (":Category:Capitals in Europe", LinkKind::Namespace),
+        ("File:Paris.jpg", LinkKind::File),
+        ("Image:Paris.jpg", LinkKind::File),
+        (":File:Paris.jpg", LinkKind::Namespace),
+        ("Wikipedia:Manual of Style", LinkKind::Namespace),
+        ("WP:NPOV", LinkKind::Namespace),
+        ("Template_talk:Infobox", LinkKind::Namespace),
+        ("fr:Paris", LinkKind::Interlanguage),
+        ("zh-yue:巴黎", LinkKind::Interlanguage),
+        (":fr:Paris", LinkKind::Interwiki),
+        ("wikt:city", LinkKind::Interwiki),
+        ("commons:Category:Paris", LinkKind::Interwiki),
+        ("s:Author:Victor Hugo", LinkKind::Interwiki),
+        // Project prefixes only match in lowercase
+        ("S: The Story", LinkKind::Article),
+        ("C:Real World", LinkKind::Article),
+        ("FR:Paris", LinkKind::Article),
+        // The wiki's own language
+        ("en:Paris", LinkKind::Article),
+        (":en:Paris", LinkKind::Article),
+        ("en:Category:Capitals in Europe", LinkKind::Namespace),
+        ("en:fr:Paris", LinkKind::Interwiki),
+    ];
+
+    for (target, kind) in cases {
+        assert_eq!(LinkKind::classify(target), kind, "{}", target);
+    }
+}
+
+#[test]
+fn classify_in_other_languages() {
+    assert_eq!(
+        LinkKind::classify_in("en:Paris", "de"),
+        LinkKind::Interlanguage
+    );
+    assert_eq!(LinkKind::classify_in("de:Berlin", "de"), LinkKind::Article);
+    assert_eq!(LinkKind::classify_in("fr:Paris", "fr"), LinkKind::Article);
+}
+
+#[test]
+fn selection() {
+    let all = LinkKind::selection(&[], &[]);
+    assert_eq!(all.len(), 6);
+
+    let articles = LinkKind::selection(
+        &[],
+        &[
+            LinkKind::Category,
+            LinkKind::File,
+            LinkKind::Namespace,
+            LinkKind::Interwiki,
+            LinkKind::Interlanguage,
+        ],
+    );
+    assert_eq!(articles, vec![LinkKind::Article]);
+
+    let some = LinkKind::selection(
+        &[LinkKind::Article, LinkKind::Category],
+        &[LinkKind::Category],
+    );
+    assert_eq!(some, vec![LinkKind::Article]);
+}
+
+#[test]
+fn links_are_classified() {
+    let links = extract_links(b"[[Paris]] [[Category:Cities]] [[fr:Paris]] [[:fr:Paris|French]]");
+    let kinds: Vec<_> = links.iter().map(|link| link.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            LinkKind::Article,
+            LinkKind::Category,
+            LinkKind::Interlanguage,
+            LinkKind::Interwiki
+        ]
+    );
+}
+
+#[test]
+fn links_in_templates() {
+    let input =
+        b"{{Infobox person\n| name = Homer\n| birth_place = [[Springfield]], [[Oregon|OR]]\n}}\n\
+        {{Main|History of Springfield|Springfield (Oregon)|l2=Oregon}}\n\
+        [[Nuclear power]] {{ill|Kernkraft|de|lt=Nuclear}} {{cite web|title=[[Title]]|url=x}}";
+    let links: Vec<_> = extract_links(input)
+        .into_iter()
+        .map(|link| (link.target, link.label, link.template, link.parameter))
+        .collect();
+
+    let link = |target: &str, label: &str, template: Option<&str>, parameter: Option<&str>| {
+        (
+            target.to_owned(),
+            label.to_owned(),
+            template.map(str::to_owned),
+            parameter.map(str::to_owned),
+        )
+    };
+    assert_eq!(
+        links,
+        vec![
+            link(
+                "Springfield",
+                "Springfield",
+                Some("Infobox person"),
+                Some("birth_place")
+            ),
+            link("Oregon", "OR", Some("Infobox person"), Some("birth_place")),
+            link(
+                "History of Springfield",
+                "History of Springfield",
+                Some("Main"),
+                Some("1")
+            ),
+            link("Springfield (Oregon)", "Oregon", Some("Main"), Some("2")),
+            link("Nuclear power", "Nuclear power", None, None),
+            link("Kernkraft", "Nuclear", Some("ill"), Some("1")),
+            link("Title", "Title", Some("cite web"), Some("title")),
+        ]
+    );
+}
+
+#[test]
+fn links_after_unclosed_templates() {
+    let input = b"{{Infobox | a = [[A]] {{Main|B}} | c = {{x|[[C]]}} \n[[D]] {{ill|E}} [[F]]";
+    let links: Vec<_> = extract_links(input)
+        .into_iter()
+        .map(|link| (link.target, link.template))
+        .collect();
+    assert_eq!(
+        links,
+        vec![
+            ("A".to_owned(), None),
+            ("B".to_owned(), Some("Main".to_owned())),
+            ("C".to_owned(), Some("x".to_owned())),
+            ("D".to_owned(), None),
+            ("E".to_owned(), Some("ill".to_owned())),
+            ("F".to_owned(), None),
+        ]
+    );
+}
+
+fn raw_article(title: &str) -> Vec<u8> {
+    std::fs::read(format!("resources/test/raw_articles/{}.txt", title)).unwrap()
+}
+
+fn targets(input: &[u8]) -> Vec<String> {
+    extract_links(input)
+        .into_iter()
+        .map(|link| link.target)
+        .collect()
+}
+
+#[test]
+fn nested_links_in_captions() {
+    let links = extract_links(&raw_article("A"));
+    let file = links
+        .iter()
+        .position(|link| link.target == "File:LowercaseA.svg")
+        .unwrap();
+
+    assert_eq!(
+        links[file].label,
+        "thumb|none|upright|Typographic variants include a double-storey '''a''' and \
+         single-storey '''ɑ'''."
+    );
+    assert_eq!(links[file + 1].target, "Allography");
+    assert_eq!(links[file + 1].label, "Typographic variants");
+}
+
+#[test]
+fn comments_are_skipped() {
+    let lincoln = targets(&raw_article("Abraham Lincoln"));
+    assert!(!lincoln.contains(&"Wikipedia:Spam".to_owned()));
+    assert!(!lincoln.contains(&"Wikipedia:External links".to_owned()));
+
+    let escaped = b"[[A]] &lt;!-- [[B]] --&gt; [[C]] <!-- [[D]]";
+    assert_eq!(targets(escaped), vec!["A", "C"]);
+}
+
+#[test]
+fn unparsed_tags_are_skipped() {
+    let input = b"[[A]] <nowiki>[[B]]</nowiki> <pre>[[C]]</pre> <nowiki/>[[D]] \
+        &lt;nowiki&gt;[[E]]&lt;/nowiki&gt; <NoWiki>[[F]]</NOWIKI> <prefix>[[G]]</prefix>";
+    assert_eq!(targets(input), vec!["A", "D", "G"]);
+
+    let links = extract_links(b"[[A|<nowiki>[[x]]</nowiki>]]");
+    assert_eq!(links.len(), 1);
+    assert_eq!(links[0].label, "[[x]]");
+}
+
+#[test]
+fn unterminated_links() {
+    // An unclosed link is plain text, links after it are still found
+    assert_eq!(targets(b"[[A\n[[B]] [[C|c\n\nd [[D]]"), vec!["B", "D"]);
+    assert_eq!(targets(b"[[A]] [[B"), vec!["A"]);
+    // An extra bracket is plain text around the link
+    let links = extract_links(b"[[[John Smith|Smith]]] [[[[B]]]] [[[C]]");
+    let links: Vec<_> = links.iter().map(|l| (&*l.target, &*l.label)).collect();
+    assert_eq!(links, vec![("John Smith", "Smith"), ("B", "B"), ("C", "C")]);
+    // Stray closing braces don't confuse later links
+    assert_eq!(targets(b"}} [[A|a}}]] [[B|{{b|c}}]]"), vec!["A", "B"]);
+}
+
+#[test]
+fn many_unclosed_links() {
+    // Each opener is tried once, instead of once per combination of the ones before it
+    let mut input = "[[a|".repeat(50_000);
+    input.push_str("\n\n[[b]]");
+    let start = std::time::Instant::now();
+    assert_eq!(targets(input.as_bytes()), vec!["b"]);
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+
+    let mut input = "[[a|[[b".repeat(10_000);
+    input.push_str("\n[[c]]");
+    assert_eq!(targets(input.as_bytes()), vec!["c"]);
+}
+
+#[test]
+fn many_comments() {
+    // Each comment only looks as far as its own closer
+    for comment in ["&lt;!-- [[x]] --&gt; ", "<!-- [[x]] --> "] {
+        let mut input = comment.repeat(20_000);
+        input.push_str("[[a]]");
+        let start = std::time::Instant::now();
+        assert_eq!(targets(input.as_bytes()), vec!["a"]);
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    }
+}
+
+#[test]
+fn deeply_nested_links() {
+    let input = format!("{}x{}", "[[a|".repeat(10_000), "]]".repeat(10_000));
+    let links = extract_links(input.as_bytes());
+    assert_eq!(links.len(), 10_000);
+    assert!(links.iter().all(|link| link.label == "x"));
+}
+
+#[test]
+fn last_links_are_found() {
+    for (title, last) in [
+        ("Anthropology", "Category:Humans"),
+        (
+            "Abraham Lincoln",
+            "Category:Assassinated former subnational legislators",
+        ),
+    ] {
+        let targets = targets(&raw_article(title));
+        assert_eq!(targets.last().map(String::as_str), Some(last), "{}", title);
+    }
+}
@@ -1,2 +1,7 @@
+pub mod categories;
+pub mod disambiguation;
+pub mod external_links;
+pub mod interlanguage;
 pub mod links;
+pub mod sections;
 pub mod wikitext;
@@ -0,0 +1,54 @@
+//! Section headings like `== History ==`, shared by the extractors that tell which section a
+//! link or entry is in.
+
+#[cfg(test)]
+mod tests;
+
+/// Level and name of the heading on `line`, like `(2, "History")` for `== History ==`. The level
+/// is the number of leading `=`, and unbalanced headings like `==History=` keep their name.
+pub fn heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim();
+    let level = line.bytes().take_while(|&b| b == b'=').count();
+    if level < 2 || !line.ends_with('=') {
+        return None;
+    }
+
+    let name = line.trim_start_matches('=').trim_end_matches('=').trim();
+    (!name.is_empty()).then_some((level, name))
+}
+
+/// The sections enclosing the current line, fed one line at a time
+#[derive(Default)]
+pub struct Sections {
+    // (level, name) of the enclosing headings, outermost first
+    stack: Vec<(usize, String)>,
+}
+
+impl Sections {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enter the section of `line` if it is a heading, leaving the sections at its level or
+    /// deeper. Returns whether it was a heading.
+    pub fn update(&mut self, line: &str) -> bool {
+        let Some((level, name)) = heading(line) else {
+            return false;
+        };
+        while self.stack.last().is_some_and(|(l, _)| *l >= level) {
+            self.stack.pop();
+        }
+        self.stack.push((level, name.to_owned()));
+        true
+    }
+
+    /// Names of the enclosing sections, outermost first
+    pub fn path(&self) -> impl Iterator<Item = &str> {
+        self.stack.iter().map(|(_, name)| name.as_str())
+    }
+
+    /// Name of the innermost section, None before the first heading
+    pub fn current(&self) -> Option<&str> {
+        self.stack.last().map(|(_, name)| name.as_str())
+    }
+}
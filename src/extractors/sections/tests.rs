@@ -0,0 +1,31 @@
+use super::{heading, Sections};
+
+#[test]
+fn headings() {
+    assert_eq!(heading("== History =="), Some((2, "History")));
+    assert_eq!(heading("  ===Early life===  "), Some((3, "Early life")));
+    // Unbalanced headings, including non-ASCII names
+    assert_eq!(heading("==Café="), Some((2, "Café")));
+    assert_eq!(heading("===Ünïcödé=="), Some((3, "Ünïcödé")));
+    assert_eq!(heading("==日本=="), Some((2, "日本")));
+
+    for line in ["= Title =", "== Not closed", "====", "text == x ==", ""] {
+        assert_eq!(heading(line), None, "{}", line);
+    }
+}
+
+#[test]
+fn nesting() {
+    let mut sections = Sections::new();
+    assert!(!sections.update("Intro"));
+    assert_eq!(sections.current(), None);
+
+    for line in ["== A ==", "=== B ===", "==== C ====", "=== D ==="] {
+        assert!(sections.update(line));
+    }
+    assert_eq!(sections.path().collect::<Vec<_>>(), vec!["A", "D"]);
+
+    sections.update("== E ==");
+    assert_eq!(sections.path().collect::<Vec<_>>(), vec!["E"]);
+    assert_eq!(sections.current(), Some("E"));
+}
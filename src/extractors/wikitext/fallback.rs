@@ -0,0 +1,101 @@
+//! A cheap markup stripper for pages the full parser can't handle in time.
+//!
+//! Runs in linear time: templates, tables, references and comments are dropped, links are
+//! replaced by their label, and other tags are removed but their contents are kept. The output is
+//! rougher than [extract_text](super::wikitext_parser::extract_text) but good enough for a page
+//! that would otherwise be missing.
+
+use html_escape::decode_html_entities;
+
+const REMOVE_LINKS: &[&str] = &["file:", "image:", "category:"];
+
+pub fn strip_markup(input: &[u8]) -> String {
+    let input = String::from_utf8_lossy(input);
+    let input = decode_html_entities(&input);
+
+    let mut output = String::with_capacity(input.len());
+    strip(&input, &mut output);
+
+    // Drop table remnants and collapse whitespace the same way the full parser does
+    let lines: Vec<_> = output
+        .lines()
+        .map(str::trim)
+        .filter(|s| !s.is_empty() && !s.starts_with('|') && !s.starts_with('!'))
+        .collect();
+    lines.join("\n")
+}
+
+fn strip(input: &str, output: &mut String) {
+    let mut rest = input;
+
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("<!--") {
+            rest = skip_past(rest, "-->");
+        } else if rest.starts_with("<ref") {
+            rest = skip_ref(rest);
+        } else if rest.starts_with("{{") || rest.starts_with("{|") {
+            rest = skip_balanced(rest, b'{', b'}');
+        } else if rest.starts_with("[[") {
+            let end = balanced_end(rest, b'[', b']');
+            let inner = rest[2..end].trim_end_matches(']');
+            let lower = inner.trim_start_matches(':').to_lowercase();
+            if !REMOVE_LINKS.iter().any(|prefix| lower.starts_with(prefix)) {
+                let label = match inner.split_once('|') {
+                    Some((_, label)) => label,
+                    None => inner,
+                };
+                strip(label, output);
+            }
+            rest = &rest[end..];
+        } else if rest.starts_with("''") {
+            rest = rest.trim_start_matches('\'');
+        } else if c == '<' {
+            // Remove the tag but keep what it encloses
+            rest = skip_past(rest, ">");
+        } else {
+            output.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+}
+
+// Return the part of input after the first occurrence of end, or nothing if there is none
+fn skip_past<'a>(input: &'a str, end: &str) -> &'a str {
+    match input.find(end) {
+        Some(i) => &input[i + end.len()..],
+        None => "",
+    }
+}
+
+fn skip_ref(input: &str) -> &str {
+    let tag_end = match input.find('>') {
+        Some(i) => i,
+        None => return "",
+    };
+    if input[..tag_end].ends_with('/') {
+        &input[tag_end + 1..]
+    } else {
+        skip_past(&input[tag_end..], "</ref>")
+    }
+}
+
+fn skip_balanced(input: &str, open: u8, close: u8) -> &str {
+    &input[balanced_end(input, open, close)..]
+}
+
+// Index just past the bracket that closes the one input starts with.
+// Unbalanced input runs to the end.
+fn balanced_end(input: &str, open: u8, close: u8) -> usize {
+    let mut depth = 0usize;
+    for (i, &b) in input.as_bytes().iter().enumerate() {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth = depth.saturating_sub(1);
+            if depth == 0 {
+                return i + 1;
+            }
+        }
+    }
+    input.len()
+}
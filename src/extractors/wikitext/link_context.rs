@@ -0,0 +1,161 @@
+//! Links together with where they appear in the plaintext produced by the contents extractor.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use super::tree::Tree;
+use super::wikitext_parser::{
+    extract_text_with_link_markers, LINK_END, LINK_SEPARATOR, LINK_START,
+};
+use crate::extractors::links::LinkKind;
+
+#[derive(Deserialize, Serialize)]
+pub struct Page {
+    pub title: String,
+    pub links: Vec<LinkContext>,
+    /// The page exceeded the size or time limits, and its links weren't extracted
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fallback: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct LinkContext {
+    pub target: String,
+    pub label: String,
+    #[serde(default)]
+    pub kind: LinkKind,
+    /// Names of the enclosing sections, starting with the article title
+    pub section: Vec<String>,
+    /// Index of the paragraph within its section
+    pub paragraph: usize,
+    /// Character offsets of the label within the paragraph, end exclusive
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn extract(input: &[u8], title: &str) -> String {
+    extract_kinds(input, title, LinkKind::value_variants())
+}
+
+/// Same as [extract], keeping only links of the given kinds
+pub fn extract_kinds(input: &[u8], title: &str, kinds: &[LinkKind]) -> String {
+    let mut links = extract_link_contexts(input, title);
+    links.retain(|link| kinds.contains(&link.kind));
+    let page = Page {
+        title: title.to_owned(),
+        links,
+        fallback: false,
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+/// Record for pages that take too long to parse. Positions only make sense relative to the full
+/// parser's output, so the page is kept without links and marked as a fallback.
+pub fn extract_fallback(_input: &[u8], title: &str) -> String {
+    let page = Page {
+        title: title.to_owned(),
+        links: Vec::new(),
+        fallback: true,
+    };
+    serde_json::ser::to_string_pretty(&page).expect("failed to serialize")
+}
+
+pub fn extract_link_contexts(input: &[u8], title: &str) -> Vec<LinkContext> {
+    let text = extract_text_with_link_markers(input);
+    let tree = Tree::from_string(title, &text);
+
+    let mut links = Vec::new();
+    let mut section = Vec::new();
+    collect(&tree, &mut section, &mut links);
+    links
+}
+
+fn collect(tree: &Tree, section: &mut Vec<String>, links: &mut Vec<LinkContext>) {
+    section.push(strip_markers(&tree.section_name));
+
+    for (paragraph, line) in tree.text.lines().enumerate() {
+        for (target, label, start, end) in locate_links(line) {
+            links.push(LinkContext {
+                kind: LinkKind::classify(&target),
+                target,
+                label,
+                section: section.clone(),
+                paragraph,
+                start,
+                end,
+            });
+        }
+    }
+
+    for child in &tree.children {
+        collect(child, section, links);
+    }
+
+    section.pop();
+}
+
+// Walk a paragraph containing link markers, and return (target, label, start, end) for every link,
+// with offsets counted in chars of the paragraph once the markers are removed.
+// Links can be nested (a link in the caption of another link), so open links are kept on a stack.
+fn locate_links(paragraph: &str) -> Vec<(String, String, usize, usize)> {
+    struct Open {
+        target: String,
+        in_target: bool,
+        start: usize,
+        label: String,
+    }
+
+    let mut links = Vec::new();
+    let mut stack: Vec<Open> = Vec::new();
+    let mut pos = 0;
+
+    for c in paragraph.chars() {
+        match c {
+            LINK_START => stack.push(Open {
+                target: String::new(),
+                in_target: true,
+                start: pos,
+                label: String::new(),
+            }),
+            LINK_SEPARATOR => {
+                if let Some(open) = stack.last_mut() {
+                    open.in_target = false;
+                    open.start = pos;
+                }
+            }
+            LINK_END => {
+                if let Some(open) = stack.pop() {
+                    links.push((open.target, open.label, open.start, pos));
+                }
+            }
+            _ => match stack.last_mut() {
+                Some(open) if open.in_target => open.target.push(c),
+                _ => {
+                    for open in stack.iter_mut().filter(|open| !open.in_target) {
+                        open.label.push(c);
+                    }
+                    pos += 1;
+                }
+            },
+        }
+    }
+
+    links.sort_by_key(|&(_, _, start, _)| start);
+    links
+}
+
+// Remove link markers, keeping the labels
+pub(super) fn strip_markers(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_target = 0;
+    for c in text.chars() {
+        match c {
+            LINK_START => in_target += 1,
+            LINK_SEPARATOR => in_target -= 1,
+            LINK_END => (),
+            _ if in_target > 0 => (),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -1,3 +1,5 @@
+pub(crate) mod fallback;
+pub mod link_context;
 mod template_transformers;
 mod tree;
 pub mod wikitext_parser;
@@ -7,3 +9,10 @@ pub fn extract(input: &[u8], title: &str) -> String {
     let tree = tree::Tree::from_string(title, &text);
     serde_json::ser::to_string_pretty(&tree).expect("failed to serialize")
 }
+
+/// Cheap version of [extract] for pages that take too long to parse
+pub fn extract_fallback(input: &[u8], title: &str) -> String {
+    let text = fallback::strip_markup(input);
+    let tree = tree::Tree::from_string(title, &text);
+    serde_json::ser::to_string_pretty(&tree).expect("failed to serialize")
+}
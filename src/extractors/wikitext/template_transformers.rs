@@ -181,7 +181,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
                 Some((k, v))
             })
             .collect();
-        let params: Vec<_> = params.values().map(|&v| v).collect();
+        let params: Vec<_> = params.values().copied().collect();
         return Some(params.join(" "));
     }
 
@@ -195,7 +195,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
                 Some((k, v))
             })
             .collect();
-        let params: Vec<_> = params.values().map(|&v| v).collect();
+        let params: Vec<_> = params.values().copied().collect();
         return Some(params.concat());
     }
 
@@ -205,7 +205,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             let s = if parts.len() == 2 {
                 format!("{} {}", parts[0], parts[1])
             } else {
-                format!("{} {} ({})", parts.get(0)?, parts.get(1)?, parts.get(2)?)
+                format!("{} {} ({})", parts.first()?, parts.get(1)?, parts.get(2)?)
             };
             return Some(s);
         }
@@ -529,7 +529,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             let mut output = if since == Some(&"y") {
                 "Since ".to_string()
             } else {
-                let as_of = if parts.get(0)?.chars().next() == Some('A') {
+                let as_of = if parts.first()?.starts_with('A') {
                     "As of"
                 } else {
                     "as of"
@@ -592,7 +592,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             .filter_map(|(k, &v)| k.parse::<usize>().ok().map(|k| (k, v)))
             .collect();
 
-        let list_items: Vec<_> = list_items.into_iter().map(|(_, v)| v).collect();
+        let list_items: Vec<_> = list_items.into_values().collect();
 
         return Some(list_items.join("\n"));
     }
@@ -629,16 +629,16 @@ pub fn filter_templates(input: &str) -> Option<String> {
         match params.len() {
             0 => return None,
             2 => {
-                let (lat_letter, lat) = if params.get(0)?.starts_with('-') {
-                    ('S', &params.get(0)?[1..])
+                let (lat_letter, lat) = if params.first()?.starts_with('-') {
+                    ('S', &params.first()?[1..])
                 } else {
-                    ('N', *params.get(0)?)
+                    ('N', *params.first()?)
                 };
 
                 let (long_letter, long) = if params.get(1)?.starts_with('-') {
-                    ('W', &params.get(0)?[1..])
+                    ('W', &params.first()?[1..])
                 } else {
-                    ('E', *params.get(0)?)
+                    ('E', *params.first()?)
                 };
 
                 return Some(format!(
@@ -649,7 +649,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             4 => {
                 return Some(format!(
                     "{}\u{00B0}{} {}\u{00B0}{}",
-                    params.get(0)?,
+                    params.first()?,
                     params.get(1)?,
                     params.get(2)?,
                     params.get(3)?
@@ -658,7 +658,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             6 => {
                 return Some(format!(
                     "{}\u{00B0}{}'{} {}\u{00B0}{}'{}",
-                    params.get(0)?,
+                    params.first()?,
                     params.get(1)?,
                     params.get(2)?,
                     params.get(3)?,
@@ -669,7 +669,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             8 => {
                 return Some(format!(
                     "{}\u{00B0}{}'{}\"{} {}\u{00B0}{}'{}\"{}",
-                    params.get(0)?,
+                    params.first()?,
                     params.get(1)?,
                     params.get(2)?,
                     params.get(3)?,
@@ -691,7 +691,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
             .map(|(k, &v)| (k.parse::<usize>().unwrap(), v))
             .collect();
 
-        let isbns: Vec<_> = params.values().map(|&v| v).collect();
+        let isbns: Vec<_> = params.values().copied().collect();
         return Some(isbns.join(", "));
     }
 
@@ -819,7 +819,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
                     .parse::<usize>()
                     .ok()
                     .and_then(|m| MONTHS.get(m - 1))
-                    .unwrap_or(&m);
+                    .unwrap_or(m);
 
                 if let Some(d) = day {
                     format!("{m} {d}, ")
@@ -827,7 +827,7 @@ pub fn filter_templates(input: &str) -> Option<String> {
                     format!("{m}, ")
                 }
             })
-            .unwrap_or(String::new());
+            .unwrap_or_default();
 
         return Some(prefix + year);
     }
@@ -919,10 +919,9 @@ pub fn filter_templates(input: &str) -> Option<String> {
         ];
         let mut vals = Vec::new();
         for name in order {
-            params
-                .get(name)
-                .filter(|t| !t.is_empty())
-                .map(|&t| vals.push(t));
+            if let Some(&t) = params.get(name).filter(|t| !t.is_empty()) {
+                vals.push(t)
+            }
         }
 
         return Some(vals.join("; "));
@@ -1031,7 +1030,7 @@ fn rename_params<'a>(
 ) -> HashMap<String, &'a str> {
     let mut counter = 1;
     for param_name in param_names {
-        if in_params.contains_key(&param_name.to_string()) {
+        if in_params.contains_key(*param_name) {
             continue;
         }
 
@@ -1041,5 +1040,5 @@ fn rename_params<'a>(
         }
         counter += 1;
     }
-    return in_params;
+    in_params
 }
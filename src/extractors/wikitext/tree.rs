@@ -1,11 +1,13 @@
 use serde::{Deserialize, Serialize};
 use std::{iter::Peekable, str::Lines};
 
+use crate::extractors::sections::heading;
+
 #[derive(Deserialize, Serialize)]
 pub struct Tree {
-    section_name: String,
-    text: String,
-    children: Vec<Tree>,
+    pub(super) section_name: String,
+    pub(super) text: String,
+    pub(super) children: Vec<Tree>,
 }
 
 impl Tree {
@@ -27,26 +29,11 @@ impl Tree {
             }
 
             // Handle headers
-            if line.starts_with("==") {
-                // Count how many = signs there are for this header
-                let new_header_depth = line.chars().take_while(|&c| c == '=').count();
-
-                // Get the header name
-                let header_chars: Vec<_> = line.chars().collect();
-                let start_index = new_header_depth;
-                let end_index = header_chars.len() - new_header_depth;
-                let header_name: String = if start_index < end_index {
-                    let header_name = &header_chars[start_index..end_index];
-                    header_name.into_iter().collect()
-                } else {
-                    "Unknown".to_string()
-                };
-
+            if let Some((new_header_depth, header_name)) = heading(line) {
                 // If there are more = signs than current level, parse child
                 if new_header_depth > level {
                     lines.next();
-                    let child =
-                        Self::from_string_worker(lines, new_header_depth, header_name.trim());
+                    let child = Self::from_string_worker(lines, new_header_depth, header_name);
 
                     // Don't add empty sections
                     if !child.text.is_empty() || !child.children.is_empty() {
@@ -1,3 +1,5 @@
+use std::cell::Cell;
+
 use html_escape::decode_html_entities;
 use regex::Regex;
 
@@ -11,6 +13,7 @@ use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom::{IResult, InputLength, Parser};
 
 use super::template_transformers::filter_templates;
+use crate::page_guard::check_deadline;
 
 const REMOVE_SECTIONS: &[&str] = &[
     "see also",
@@ -30,6 +33,28 @@ const REMOVE_SECTIONS: &[&str] = &[
 
 const REMOVE_LINKS: &[&str] = &["file:", "image:", "category:"];
 
+// Private use characters that wrap each link in the output when link markers are enabled:
+// LINK_START target LINK_SEPARATOR label LINK_END
+pub const LINK_START: char = '\u{E000}';
+pub const LINK_SEPARATOR: char = '\u{E001}';
+pub const LINK_END: char = '\u{E002}';
+
+thread_local! {
+    static EMIT_LINK_MARKERS: Cell<bool> = const { Cell::new(false) };
+}
+
+// Same as extract_text, but every link label in the output is wrapped in link markers together
+// with the link target, so links can be located in the plaintext afterwards
+pub fn extract_text_with_link_markers(input: &[u8]) -> String {
+    let input = String::from_utf8_lossy(input).replace([LINK_START, LINK_SEPARATOR, LINK_END], "");
+
+    EMIT_LINK_MARKERS.with(|m| m.set(true));
+    let output = std::panic::catch_unwind(|| extract_text(input.as_bytes()));
+    EMIT_LINK_MARKERS.with(|m| m.set(false));
+
+    output.unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+}
+
 // Take a given wikitext-formatted string and extract the useful text
 pub fn extract_text(input: &[u8]) -> String {
     let input = String::from_utf8_lossy(input);
@@ -81,6 +106,7 @@ fn article_parser(input: &str) -> String {
 
 // If next item is special, parse it. Otherwise, move forward one char
 fn general_content_parser(input: &str) -> IResult<&str, String> {
+    check_deadline();
     alt((
         table_parser,
         template_parser,
@@ -278,7 +304,7 @@ fn template_parser(input: &str) -> IResult<&str, String> {
             let input = input.concat();
             let (_, reparsed_input) = template_contents_parser(&input).unwrap();
             let output = filter_templates(&reparsed_input);
-            output.unwrap_or(String::new())
+            output.unwrap_or_default()
         },
     )(input)
 }
@@ -326,10 +352,14 @@ fn link_parser(input: &str) -> IResult<&str, String> {
             {
                 String::new()
             } else {
-                if let Some((_, out)) = s.split_once('|') {
-                    out.to_string()
+                let (target, label) = s.split_once('|').unwrap_or((&s, &s));
+                if EMIT_LINK_MARKERS.with(Cell::get) {
+                    // Surrounding whitespace is collapsed or trimmed in the final text, which
+                    // would shift the label relative to the markers
+                    let label = label.trim();
+                    format!("{LINK_START}{target}{LINK_SEPARATOR}{label}{LINK_END}")
                 } else {
-                    s
+                    label.to_string()
                 }
             }
         },
@@ -541,5 +571,12 @@ where
     E: ParseError<I>,
     I: Clone + InputLength,
 {
+    // Backtracking over the body is what makes pathological pages slow, so give the page guard
+    // a chance to stop us here
+    let mut body = body;
+    let body = move |input| {
+        check_deadline();
+        body.parse(input)
+    };
     map(preceded(start, many_till(body, end)), |(o1, _)| o1)
 }
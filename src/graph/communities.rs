@@ -0,0 +1,206 @@
+//! Community detection by label propagation.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+};
+
+use clap::ValueEnum;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::{Adjacency, CsrGraph, NodeId};
+
+/// Which links connect two pages of a community
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Projection {
+    /// A link in either direction
+    #[default]
+    Undirected,
+    /// Links in both directions, which are much less likely to be incidental
+    Reciprocal,
+}
+
+/// `graph` without directions: every pair of pages linked as `projection` says is joined once
+/// each way, without links from a page to itself. `reversed` must be `graph.reversed()`.
+pub fn project(graph: &CsrGraph, reversed: &CsrGraph, projection: Projection) -> CsrGraph {
+    let adjacency: Vec<Vec<NodeId>> = (0..graph.num_nodes() as NodeId)
+        .into_par_iter()
+        .map(|node| {
+            let mut outbound: Vec<_> = graph.neighbors(node).collect();
+            outbound.sort_unstable();
+            // Already sorted, as the reversed graph is built in node order
+            let inbound: Vec<_> = reversed.neighbors(node).collect();
+
+            let mut neighbors = match projection {
+                Projection::Undirected => {
+                    let mut neighbors = outbound;
+                    neighbors.extend(inbound);
+                    neighbors.sort_unstable();
+                    neighbors.dedup();
+                    neighbors
+                }
+                Projection::Reciprocal => outbound
+                    .into_iter()
+                    .filter(|target| inbound.binary_search(target).is_ok())
+                    .collect(),
+            };
+            neighbors.retain(|&neighbor| neighbor != node);
+            neighbors
+        })
+        .collect();
+
+    let titles: Vec<_> = graph.nodes().map(|node| graph.title(node)).collect();
+    CsrGraph::build(&titles, &adjacency)
+}
+
+pub struct LabelPropagationConfig {
+    pub max_iterations: usize,
+    /// Stop once fewer than this share of the nodes change community in an iteration
+    pub min_changes: f64,
+    pub seed: u64,
+}
+
+impl Default for LabelPropagationConfig {
+    fn default() -> Self {
+        Self {
+            max_iterations: 20,
+            min_changes: 0.001,
+            seed: 0,
+        }
+    }
+}
+
+/// Community of every node of an undirected `graph`, numbered from 0 by decreasing size.
+///
+/// Every node starts in a community of its own and repeatedly joins the community most of its
+/// neighbours are in, keeping its own on ties if it can. Nodes are visited in a random order, and
+/// in parallel, each seeing the latest communities of its neighbours, so results vary slightly
+/// between runs on several threads.
+pub fn label_propagation(
+    graph: &(impl Adjacency + Sync),
+    config: &LabelPropagationConfig,
+) -> Vec<u32> {
+    let num_nodes = graph.num_nodes();
+    let labels: Vec<AtomicU32> = (0..num_nodes as u32).map(AtomicU32::new).collect();
+    let mut order: Vec<NodeId> = (0..num_nodes as NodeId).collect();
+    let mut rng = fastrand::Rng::with_seed(config.seed);
+
+    for iteration in 0..config.max_iterations {
+        rng.shuffle(&mut order);
+        let changes = AtomicUsize::new(0);
+
+        order
+            .par_iter()
+            .for_each_init(Vec::new, |neighbor_labels, &node| {
+                neighbor_labels.clear();
+                neighbor_labels.extend(
+                    graph
+                        .neighbors(node)
+                        .map(|neighbor| labels[neighbor as usize].load(Ordering::Relaxed)),
+                );
+                let current = labels[node as usize].load(Ordering::Relaxed);
+                let tie_break = config.seed ^ ((iteration as u64) << 32 | node as u64);
+                if let Some(label) = most_common(neighbor_labels, current, tie_break) {
+                    if label != current {
+                        labels[node as usize].store(label, Ordering::Relaxed);
+                        changes.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            });
+
+        let changes = changes.into_inner();
+        if (changes as f64) < config.min_changes * num_nodes as f64 {
+            break;
+        }
+    }
+
+    renumber(labels.into_iter().map(AtomicU32::into_inner))
+}
+
+// The most frequent of `labels`, preferring `current` and otherwise picking among the ties with
+// `tie_break`, or None without labels
+fn most_common(labels: &mut [u32], current: u32, tie_break: u64) -> Option<u32> {
+    labels.sort_unstable();
+    let mut best_count = 0;
+    let mut best = Vec::new();
+    for run in labels.chunk_by(|a, b| a == b) {
+        match run.len().cmp(&best_count) {
+            std::cmp::Ordering::Greater => {
+                best_count = run.len();
+                best.clear();
+                best.push(run[0]);
+            }
+            std::cmp::Ordering::Equal => best.push(run[0]),
+            std::cmp::Ordering::Less => {}
+        }
+    }
+
+    if best.contains(&current) {
+        return Some(current);
+    }
+    let pick = fastrand::Rng::with_seed(tie_break).usize(..best.len().max(1));
+    best.get(pick).copied()
+}
+
+// Number labels from 0 by decreasing community size, then by label
+fn renumber(labels: impl Iterator<Item = u32>) -> Vec<u32> {
+    let labels: Vec<_> = labels.collect();
+    let mut sizes: HashMap<u32, usize> = HashMap::new();
+    for &label in &labels {
+        *sizes.entry(label).or_default() += 1;
+    }
+    let mut by_size: Vec<_> = sizes.into_iter().collect();
+    by_size.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let ids: HashMap<u32, u32> = by_size
+        .into_iter()
+        .enumerate()
+        .map(|(id, (label, _))| (label, id as u32))
+        .collect();
+
+    labels.into_iter().map(|label| ids[&label]).collect()
+}
+
+/// A community with its best known pages
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Community<'a> {
+    pub community: u32,
+    pub size: usize,
+    pub top_members: Vec<&'a str>,
+}
+
+/// Communities with at least `min_size` pages, largest first, listing their `top` members with
+/// the highest `ranks` (e.g. backlink counts)
+pub fn summarize<'a>(
+    graph: &'a CsrGraph,
+    communities: &[u32],
+    ranks: &[usize],
+    min_size: usize,
+    top: usize,
+) -> Vec<Community<'a>> {
+    let num_communities = communities.iter().max().map_or(0, |&max| max as usize + 1);
+    let mut members: Vec<Vec<NodeId>> = vec![Vec::new(); num_communities];
+    for (node, &community) in communities.iter().enumerate() {
+        members[community as usize].push(node as NodeId);
+    }
+
+    members
+        .into_iter()
+        .enumerate()
+        .filter(|(_, members)| members.len() >= min_size)
+        .map(|(community, mut members)| {
+            members.sort_unstable_by(|&a, &b| {
+                ranks[b as usize].cmp(&ranks[a as usize]).then(a.cmp(&b))
+            });
+            Community {
+                community: community as u32,
+                size: members.len(),
+                top_members: members
+                    .into_iter()
+                    .take(top)
+                    .map(|node| graph.title(node))
+                    .collect(),
+            }
+        })
+        .collect()
+}
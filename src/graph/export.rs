@@ -0,0 +1,288 @@
+//! Writing subgraphs with their links in formats graph tools read.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use html_escape::encode_double_quoted_attribute as xml_escape;
+
+use super::{CsrGraph, NodeId};
+
+/// File format of an exported subgraph
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// `source<TAB>target` titles, one line per link
+    #[default]
+    Tsv,
+    /// GraphML, read by NetworkX, Gephi and yEd
+    Graphml,
+    /// GEXF, Gephi's own format
+    Gexf,
+    /// Graphviz DOT
+    Dot,
+}
+
+/// A page of a subgraph, with what the method that found it knows about it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Node {
+    pub node: NodeId,
+    /// Links from the closest root
+    pub depth: Option<u32>,
+    /// Personalized PageRank or number of random walk visits
+    pub score: Option<f64>,
+}
+
+/// Pages of the whole graph together with all the links between them
+pub struct Subgraph<'a> {
+    graph: &'a CsrGraph,
+    nodes: Vec<Node>,
+    /// Links as positions in `nodes`
+    edges: Vec<(usize, usize)>,
+    in_degrees: Vec<usize>,
+    out_degrees: Vec<usize>,
+}
+
+impl<'a> Subgraph<'a> {
+    /// The subgraph induced by `nodes`: every link of `graph` between two of them. Nodes keep
+    /// their order, and repeated nodes are dropped.
+    pub fn induced(graph: &'a CsrGraph, nodes: impl IntoIterator<Item = Node>) -> Self {
+        let mut positions: HashMap<NodeId, usize> = HashMap::new();
+        let mut unique = Vec::new();
+        for node in nodes {
+            if let Entry::Vacant(entry) = positions.entry(node.node) {
+                entry.insert(unique.len());
+                unique.push(node);
+            }
+        }
+        let nodes = unique;
+
+        let mut edges = Vec::new();
+        let mut in_degrees = vec![0; nodes.len()];
+        let mut out_degrees = vec![0; nodes.len()];
+        for (source, node) in nodes.iter().enumerate() {
+            for neighbor in graph.neighbors(node.node) {
+                if let Some(&target) = positions.get(&neighbor) {
+                    edges.push((source, target));
+                    out_degrees[source] += 1;
+                    in_degrees[target] += 1;
+                }
+            }
+        }
+
+        Self {
+            graph,
+            nodes,
+            edges,
+            in_degrees,
+            out_degrees,
+        }
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn write_file(&self, filename: impl AsRef<Path>, format: Format) -> io::Result<()> {
+        if let Some(parent) = filename.as_ref().parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        self.write(format, BufWriter::new(File::create(filename)?))
+    }
+
+    pub fn write(&self, format: Format, writer: impl Write) -> io::Result<()> {
+        match format {
+            Format::Tsv => self.write_tsv(writer),
+            Format::Graphml => self.write_graphml(writer),
+            Format::Gexf => self.write_gexf(writer),
+            Format::Dot => self.write_dot(writer),
+        }
+    }
+
+    fn title(&self, position: usize) -> &str {
+        self.graph.title(self.nodes[position].node)
+    }
+
+    fn write_tsv(&self, mut writer: impl Write) -> io::Result<()> {
+        for &(source, target) in &self.edges {
+            writeln!(writer, "{}\t{}", self.title(source), self.title(target))?;
+        }
+        writer.flush()
+    }
+
+    fn write_graphml(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+        )?;
+        writeln!(
+            writer,
+            r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#
+        )?;
+        for (name, kind) in ATTRIBUTES {
+            writeln!(
+                writer,
+                r#"  <key id="{0}" for="node" attr.name="{0}" attr.type="{1}"/>"#,
+                name,
+                kind.graphml()
+            )?;
+        }
+        writeln!(writer, r#"  <graph id="subgraph" edgedefault="directed">"#)?;
+
+        for position in 0..self.nodes.len() {
+            writeln!(writer, r#"    <node id="n{}">"#, position)?;
+            writeln!(
+                writer,
+                r#"      <data key="label">{}</data>"#,
+                xml_escape(self.title(position))
+            )?;
+            for (name, value) in self.attributes(position) {
+                writeln!(writer, r#"      <data key="{}">{}</data>"#, name, value)?;
+            }
+            writeln!(writer, "    </node>")?;
+        }
+        for &(source, target) in &self.edges {
+            writeln!(
+                writer,
+                r#"    <edge source="n{}" target="n{}"/>"#,
+                source, target
+            )?;
+        }
+
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</graphml>")?;
+        writer.flush()
+    }
+
+    fn write_gexf(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(
+            writer,
+            r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#
+        )?;
+        writeln!(writer, r#"  <graph defaultedgetype="directed">"#)?;
+        writeln!(writer, r#"    <attributes class="node">"#)?;
+        for (name, kind) in ATTRIBUTES {
+            writeln!(
+                writer,
+                r#"      <attribute id="{0}" title="{0}" type="{1}"/>"#,
+                name,
+                kind.gexf()
+            )?;
+        }
+        writeln!(writer, "    </attributes>")?;
+
+        writeln!(writer, "    <nodes>")?;
+        for position in 0..self.nodes.len() {
+            writeln!(
+                writer,
+                r#"      <node id="{}" label="{}">"#,
+                position,
+                xml_escape(self.title(position))
+            )?;
+            writeln!(writer, "        <attvalues>")?;
+            for (name, value) in self.attributes(position) {
+                writeln!(
+                    writer,
+                    r#"          <attvalue for="{}" value="{}"/>"#,
+                    name, value
+                )?;
+            }
+            writeln!(writer, "        </attvalues>")?;
+            writeln!(writer, "      </node>")?;
+        }
+        writeln!(writer, "    </nodes>")?;
+
+        writeln!(writer, "    <edges>")?;
+        for (id, &(source, target)) in self.edges.iter().enumerate() {
+            writeln!(
+                writer,
+                r#"      <edge id="{}" source="{}" target="{}"/>"#,
+                id, source, target
+            )?;
+        }
+        writeln!(writer, "    </edges>")?;
+
+        writeln!(writer, "  </graph>")?;
+        writeln!(writer, "</gexf>")?;
+        writer.flush()
+    }
+
+    fn write_dot(&self, mut writer: impl Write) -> io::Result<()> {
+        writeln!(writer, "digraph links {{")?;
+        for position in 0..self.nodes.len() {
+            write!(
+                writer,
+                "  n{} [label=\"{}\"",
+                position,
+                dot_escape(self.title(position))
+            )?;
+            for (name, value) in self.attributes(position) {
+                write!(writer, ", {}={}", name, value)?;
+            }
+            writeln!(writer, "];")?;
+        }
+        for &(source, target) in &self.edges {
+            writeln!(writer, "  n{} -> n{};", source, target)?;
+        }
+        writeln!(writer, "}}")?;
+        writer.flush()
+    }
+
+    // Values of the `ATTRIBUTES` the node has
+    fn attributes(&self, position: usize) -> Vec<(&'static str, String)> {
+        let node = &self.nodes[position];
+        let mut attributes = Vec::new();
+        if let Some(depth) = node.depth {
+            attributes.push(("depth", depth.to_string()));
+        }
+        if let Some(score) = node.score {
+            attributes.push(("score", score.to_string()));
+        }
+        attributes.push(("in_degree", self.in_degrees[position].to_string()));
+        attributes.push(("out_degree", self.out_degrees[position].to_string()));
+        attributes
+    }
+}
+
+#[derive(Clone, Copy)]
+enum AttributeType {
+    Integer,
+    Double,
+}
+
+impl AttributeType {
+    fn graphml(self) -> &'static str {
+        match self {
+            AttributeType::Integer => "int",
+            AttributeType::Double => "double",
+        }
+    }
+
+    fn gexf(self) -> &'static str {
+        match self {
+            AttributeType::Integer => "integer",
+            AttributeType::Double => "double",
+        }
+    }
+}
+
+// Node attributes, with degrees counting links within the subgraph
+const ATTRIBUTES: [(&str, AttributeType); 4] = [
+    ("depth", AttributeType::Integer),
+    ("score", AttributeType::Double),
+    ("in_degree", AttributeType::Integer),
+    ("out_degree", AttributeType::Integer),
+];
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,522 @@
+//! The resolved link graph as compressed sparse row (CSR) arrays.
+//!
+//! Nodes are pages, numbered in index order, and edges are links between pages that both exist.
+//! The file is laid out so it can be memory mapped and queried without parsing:
+//!
+//! ```text
+//! header         magic, number of nodes, number of edges, length of the title bytes (u64 each)
+//! offsets        u64 × (nodes + 1)  edges of node i are targets[offsets[i]..offsets[i + 1]]
+//! targets        u32 × edges
+//! title offsets  u64 × (nodes + 1)  title of node i is titles[title_offsets[i]..title_offsets[i + 1]]
+//! sorted ids     u32 × nodes        node ids ordered by title, for lookups by title
+//! titles         UTF-8 bytes
+//! ```
+//!
+//! Arrays are little endian and start at multiples of 8 bytes.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::Deref,
+    path::Path,
+};
+
+use clap::ValueEnum;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use regex::RegexSet;
+
+use crate::dataset::{self, PageIndex};
+use crate::extractors::links::{LinkKind, Page};
+use crate::par_file::ParFileConfig;
+use crate::progress::ProgressReporter;
+use crate::wiki::normalize_title;
+
+pub mod communities;
+pub mod export;
+pub mod pagerank;
+pub mod paths;
+pub mod stats;
+pub mod subgraph;
+
+#[cfg(test)]
+mod tests;
+
+const MAGIC: &[u8; 8] = b"WPCSR001";
+const HEADER_SIZE: usize = 32;
+
+pub type NodeId = u32;
+
+/// Which links a traversal follows
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Links from a page
+    #[default]
+    Outbound,
+    /// Links to a page
+    Inbound,
+    /// Links in either direction
+    Undirected,
+}
+
+impl Direction {
+    /// The direction that follows the same links backwards
+    pub fn reversed(self) -> Self {
+        match self {
+            Direction::Outbound => Direction::Inbound,
+            Direction::Inbound => Direction::Outbound,
+            Direction::Undirected => Direction::Undirected,
+        }
+    }
+}
+
+/// What traversals need from a graph
+pub trait Adjacency {
+    fn num_nodes(&self) -> usize;
+    fn neighbors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_;
+    fn degree(&self, node: NodeId) -> usize;
+}
+
+/// A graph seen in one [Direction], using the reversed graph for inbound links
+pub struct Directed<'a> {
+    outbound: Option<&'a CsrGraph>,
+    inbound: Option<&'a CsrGraph>,
+}
+
+impl<'a> Directed<'a> {
+    /// `reversed` must be `graph.reversed()` unless `direction` is outbound
+    pub fn new(graph: &'a CsrGraph, reversed: Option<&'a CsrGraph>, direction: Direction) -> Self {
+        let needs_reversed = direction != Direction::Outbound;
+        assert!(
+            !needs_reversed || reversed.is_some(),
+            "{:?} traversal needs the reversed graph",
+            direction
+        );
+
+        Self {
+            outbound: (direction != Direction::Inbound).then_some(graph),
+            inbound: reversed.filter(|_| needs_reversed),
+        }
+    }
+}
+
+impl Adjacency for Directed<'_> {
+    fn num_nodes(&self) -> usize {
+        self.outbound
+            .or(self.inbound)
+            .map_or(0, CsrGraph::num_nodes)
+    }
+
+    fn neighbors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.outbound
+            .into_iter()
+            .chain(self.inbound)
+            .flat_map(move |graph| graph.neighbors(node))
+    }
+
+    fn degree(&self, node: NodeId) -> usize {
+        self.outbound
+            .into_iter()
+            .chain(self.inbound)
+            .map(|graph| graph.degree(node))
+            .sum()
+    }
+}
+
+impl Adjacency for CsrGraph {
+    fn num_nodes(&self) -> usize {
+        self.num_nodes()
+    }
+
+    fn neighbors(&self, node: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.neighbors(node)
+    }
+
+    fn degree(&self, node: NodeId) -> usize {
+        self.degree(node)
+    }
+}
+
+pub struct CsrGraph {
+    bytes: Storage,
+    num_nodes: usize,
+    num_edges: usize,
+    offsets: usize,
+    targets: usize,
+    title_offsets: usize,
+    sorted_ids: usize,
+    titles: usize,
+}
+
+enum Storage {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for Storage {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Storage::Mapped(mmap) => mmap,
+            Storage::Owned(bytes) => bytes,
+        }
+    }
+}
+
+impl CsrGraph {
+    /// Memory map a graph file written by [write]
+    pub fn open(filename: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(filename)?;
+        // Safety: the file must not be modified while it's mapped, like any other input file
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::from_storage(Storage::Mapped(mmap))
+    }
+
+    /// Build a graph in memory. `adjacency[i]` lists the nodes that node `i` links to.
+    pub fn build<S: AsRef<str>>(titles: &[S], adjacency: &[Vec<NodeId>]) -> Self {
+        let mut bytes = Vec::new();
+        write(&mut bytes, titles, adjacency).expect("writing to memory can't fail");
+        Self::from_storage(Storage::Owned(bytes)).expect("freshly written graph is valid")
+    }
+
+    fn from_storage(bytes: Storage) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(invalid("not a graph file"));
+        }
+        let num_nodes = read_u64(&bytes, 8) as usize;
+        let num_edges = read_u64(&bytes, 16) as usize;
+        let titles_len = read_u64(&bytes, 24) as usize;
+
+        let offsets = HEADER_SIZE;
+        let targets = offsets + 8 * (num_nodes + 1);
+        let title_offsets = align(targets + 4 * num_edges);
+        let sorted_ids = title_offsets + 8 * (num_nodes + 1);
+        let titles = align(sorted_ids + 4 * num_nodes);
+        if bytes.len() != titles + titles_len {
+            return Err(invalid("graph file has the wrong size"));
+        }
+
+        Ok(Self {
+            bytes,
+            num_nodes,
+            num_edges,
+            offsets,
+            targets,
+            title_offsets,
+            sorted_ids,
+            titles,
+        })
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.num_nodes
+    }
+
+    pub fn num_edges(&self) -> usize {
+        self.num_edges
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.num_nodes as NodeId
+    }
+
+    /// Nodes that `node` links to
+    pub fn neighbors(&self, node: NodeId) -> impl ExactSizeIterator<Item = NodeId> + '_ {
+        let start = self.offset(node);
+        let end = self.offset(node + 1);
+        (start..end).map(|i| read_u32(&self.bytes, self.targets + 4 * i))
+    }
+
+    /// Number of links from `node`
+    pub fn degree(&self, node: NodeId) -> usize {
+        self.offset(node + 1) - self.offset(node)
+    }
+
+    pub fn title(&self, node: NodeId) -> &str {
+        let start = read_u64(&self.bytes, self.title_offsets + 8 * node as usize) as usize;
+        let end = read_u64(&self.bytes, self.title_offsets + 8 * (node as usize + 1)) as usize;
+        std::str::from_utf8(&self.bytes[self.titles + start..self.titles + end])
+            .expect("titles are valid UTF-8")
+    }
+
+    /// Look a node up by its title, with a binary search over the sorted titles
+    pub fn node(&self, title: &str) -> Option<NodeId> {
+        let (mut low, mut high) = (0, self.num_nodes);
+        while low < high {
+            let mid = (low + high) / 2;
+            let node = read_u32(&self.bytes, self.sorted_ids + 4 * mid);
+            match self.title(node).cmp(title) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(node),
+            }
+        }
+        None
+    }
+
+    /// Number of links to every node, without reversing the graph
+    pub fn in_degrees(&self) -> Vec<u32> {
+        let mut in_degrees = vec![0; self.num_nodes];
+        for node in self.nodes() {
+            for target in self.neighbors(node) {
+                in_degrees[target as usize] += 1;
+            }
+        }
+        in_degrees
+    }
+
+    /// Whether the title of every node matches any of `patterns`
+    pub fn titles_matching(&self, patterns: &RegexSet) -> Vec<bool> {
+        (0..self.num_nodes as NodeId)
+            .into_par_iter()
+            .map(|node| patterns.is_match(self.title(node)))
+            .collect()
+    }
+
+    /// The same graph with every link reversed, so the neighbors of a page are the pages linking
+    /// to it
+    pub fn reversed(&self) -> CsrGraph {
+        CsrGraph::build(&self.titles(), &self.reversed_adjacency())
+    }
+
+    /// Write the reversed graph next to `filename`, the file of this graph, where
+    /// [open_reversed] finds it
+    pub fn write_reversed_file(&self, filename: &str) -> io::Result<()> {
+        write_file(
+            &reversed_path(filename),
+            &self.titles(),
+            &self.reversed_adjacency(),
+        )
+    }
+
+    fn titles(&self) -> Vec<&str> {
+        self.nodes().map(|node| self.title(node)).collect()
+    }
+
+    fn reversed_adjacency(&self) -> Vec<Vec<NodeId>> {
+        let mut adjacency = vec![Vec::new(); self.num_nodes];
+        for node in self.nodes() {
+            for target in self.neighbors(node) {
+                adjacency[target as usize].push(node);
+            }
+        }
+        adjacency
+    }
+
+    fn offset(&self, node: NodeId) -> usize {
+        read_u64(&self.bytes, self.offsets + 8 * node as usize) as usize
+    }
+}
+
+/// File holding the reversed graph of the graph file `filename`
+pub fn reversed_path(filename: &str) -> String {
+    format!("{}.reversed", filename)
+}
+
+/// Open the reversed graph of `graph` written next to its file `filename` by
+/// [CsrGraph::write_reversed_file]. Returns None if there is none, or if it is older than the
+/// graph file or doesn't match the graph, so the graph must be reversed in memory.
+pub fn open_reversed(filename: &str, graph: &CsrGraph) -> io::Result<Option<CsrGraph>> {
+    let path = reversed_path(filename);
+    let modified = |path: &str| std::fs::metadata(path)?.modified();
+    match modified(&path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+        Ok(reversed_modified) if reversed_modified < modified(filename)? => return Ok(None),
+        Ok(_) => (),
+    }
+
+    let reversed = CsrGraph::open(&path)?;
+    let matches =
+        reversed.num_nodes() == graph.num_nodes() && reversed.num_edges() == graph.num_edges();
+    Ok(matches.then_some(reversed))
+}
+
+/// Write a graph in the format read by [CsrGraph::open]
+pub fn write<S: AsRef<str>>(
+    writer: impl Write,
+    titles: &[S],
+    adjacency: &[Vec<NodeId>],
+) -> io::Result<()> {
+    assert_eq!(titles.len(), adjacency.len());
+    assert!(titles.len() < NodeId::MAX as usize, "too many nodes");
+
+    let mut writer = CountingWriter::new(writer);
+    let num_edges: usize = adjacency.iter().map(Vec::len).sum();
+    let titles_len: usize = titles.iter().map(|title| title.as_ref().len()).sum();
+
+    writer.write_all(MAGIC)?;
+    for n in [titles.len(), num_edges, titles_len] {
+        writer.write_all(&(n as u64).to_le_bytes())?;
+    }
+
+    let mut offset = 0u64;
+    writer.write_all(&offset.to_le_bytes())?;
+    for targets in adjacency {
+        offset += targets.len() as u64;
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+    for &target in adjacency.iter().flatten() {
+        writer.write_all(&target.to_le_bytes())?;
+    }
+    writer.pad()?;
+
+    let mut offset = 0u64;
+    writer.write_all(&offset.to_le_bytes())?;
+    for title in titles {
+        offset += title.as_ref().len() as u64;
+        writer.write_all(&offset.to_le_bytes())?;
+    }
+
+    let mut sorted_ids: Vec<NodeId> = (0..titles.len() as NodeId).collect();
+    sorted_ids.sort_by_key(|&id| titles[id as usize].as_ref());
+    for id in sorted_ids {
+        writer.write_all(&id.to_le_bytes())?;
+    }
+    writer.pad()?;
+
+    for title in titles {
+        writer.write_all(title.as_ref().as_bytes())?;
+    }
+    writer.flush()
+}
+
+/// Write a graph to `filename`, creating its folder if needed
+pub fn write_file<S: AsRef<str>>(
+    filename: &str,
+    titles: &[S],
+    adjacency: &[Vec<NodeId>],
+) -> io::Result<()> {
+    if let Some(parent) = Path::new(filename).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write(BufWriter::new(File::create(filename)?), titles, adjacency)
+}
+
+/// Resolve the output of the links extractor into adjacency lists. Only article links are kept,
+/// links to pages that aren't in the index are dropped, and every link appears once per page.
+pub fn adjacency_from_links(
+    filename: &str,
+    index_list: &[PageIndex],
+    par_file_config: ParFileConfig,
+    progress_reporter: Box<dyn ProgressReporter>,
+) -> Vec<Vec<NodeId>> {
+    resolve_links(filename, index_list, par_file_config, progress_reporter).adjacency
+}
+
+/// Links extractor output resolved against the index
+pub struct ResolvedLinks {
+    pub adjacency: Vec<Vec<NodeId>>,
+    /// Article links to pages that aren't in the index
+    pub dangling_links: u64,
+}
+
+/// [adjacency_from_links], also counting the links it drops
+pub fn resolve_links(
+    filename: &str,
+    index_list: &[PageIndex],
+    par_file_config: ParFileConfig,
+    progress_reporter: Box<dyn ProgressReporter>,
+) -> ResolvedLinks {
+    let index_map: HashMap<&str, NodeId> = index_list
+        .iter()
+        .map(|page_index| (page_index.title.as_str(), page_index.idx as NodeId))
+        .collect();
+
+    let (pages, dangling_links) = dataset::fold_pages(
+        filename,
+        index_list,
+        par_file_config,
+        progress_reporter,
+        || (Vec::new(), 0),
+        |(mut pages, dangling_links), page_index, page: Page| {
+            let (targets, dangling) = page_targets(&page, &index_map);
+            pages.push((page_index.idx, targets));
+            (pages, dangling_links + dangling)
+        },
+        |(mut pages, dangling_links), (other_pages, other_dangling_links)| {
+            pages.extend(other_pages);
+            (pages, dangling_links + other_dangling_links)
+        },
+    );
+
+    let mut adjacency = vec![Vec::new(); index_list.len()];
+    for (idx, targets) in pages {
+        adjacency[idx] = targets;
+    }
+    ResolvedLinks {
+        adjacency,
+        dangling_links,
+    }
+}
+
+// Nodes the article links of `page` point to, once each, and the number of article links to
+// pages that aren't in `index_map`. Categories, files and other wikis aren't pages of the graph,
+// even when a page has the same title.
+fn page_targets(page: &Page, index_map: &HashMap<&str, NodeId>) -> (Vec<NodeId>, u64) {
+    let mut targets = Vec::with_capacity(page.links.len());
+    let mut seen = HashSet::new();
+    let mut dangling_links = 0;
+    for link in &page.links {
+        if link.kind != LinkKind::Article {
+            continue;
+        }
+        let target = match index_map.get(link.target.as_str()) {
+            Some(&target) => Some(target),
+            None => index_map
+                .get(normalize_title(&link.target).as_str())
+                .copied(),
+        };
+        match target {
+            Some(target) if seen.insert(target) => targets.push(target),
+            Some(_) => {}
+            None => dangling_links += 1,
+        }
+    }
+    (targets, dangling_links)
+}
+
+fn align(position: usize) -> usize {
+    position.next_multiple_of(8)
+}
+
+fn read_u64(bytes: &[u8], position: usize) -> u64 {
+    u64::from_le_bytes(bytes[position..position + 8].try_into().unwrap())
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap())
+}
+
+// Keeps track of the position so sections can be padded to 8 bytes
+struct CountingWriter<W: Write> {
+    inner: W,
+    position: usize,
+}
+
+impl<W: Write> CountingWriter<W> {
+    fn new(inner: W) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    fn pad(&mut self) -> io::Result<()> {
+        let padding = align(self.position) - self.position;
+        self.write_all(&[0; 8][..padding])
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.position += n;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
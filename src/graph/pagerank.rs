@@ -0,0 +1,118 @@
+//! PageRank and personalized PageRank.
+
+use rayon::prelude::*;
+
+use super::{Adjacency, NodeId};
+
+pub struct PageRankConfig {
+    /// Probability of following a link rather than jumping to a random (or seed) page
+    pub damping: f64,
+    pub max_iterations: usize,
+    /// Stop once the scores change by less than this in total over an iteration
+    pub tolerance: f64,
+}
+
+impl Default for PageRankConfig {
+    fn default() -> Self {
+        Self {
+            damping: 0.85,
+            max_iterations: 100,
+            tolerance: 1e-9,
+        }
+    }
+}
+
+/// PageRank score of every node, summing to 1. `reversed` must be the reverse of `graph`, so
+/// every node can sum what flows in over its inbound links, in parallel.
+///
+/// With no `seeds` random jumps land on any page. Otherwise they land on one of the seeds, which
+/// gives the personalized PageRank of the seeds: how relevant every page is to them. Pages
+/// without links jump the same way.
+pub fn pagerank<G, R>(
+    graph: &G,
+    reversed: &R,
+    seeds: &[NodeId],
+    config: &PageRankConfig,
+) -> Vec<f64>
+where
+    G: Adjacency + Sync,
+    R: Adjacency + Sync,
+{
+    let num_nodes = graph.num_nodes();
+    let mut jump = vec![0.0; num_nodes];
+    if seeds.is_empty() {
+        jump.fill(1.0 / num_nodes as f64);
+    } else {
+        for &seed in seeds {
+            jump[seed as usize] += 1.0 / seeds.len() as f64;
+        }
+    }
+
+    let degrees: Vec<usize> = (0..num_nodes as NodeId)
+        .into_par_iter()
+        .map(|node| graph.degree(node))
+        .collect();
+    let mut scores = jump.clone();
+    let mut shares = vec![0.0; num_nodes];
+
+    for _ in 0..config.max_iterations {
+        // What every node passes on over each of its links
+        shares
+            .par_iter_mut()
+            .zip(scores.par_iter().zip(&degrees))
+            .for_each(|(share, (&score, &degree))| {
+                *share = if degree == 0 {
+                    0.0
+                } else {
+                    score / degree as f64
+                };
+            });
+        let dangling: f64 = scores
+            .par_iter()
+            .zip(&degrees)
+            .filter(|(_, &degree)| degree == 0)
+            .map(|(&score, _)| score)
+            .sum();
+
+        let next: Vec<f64> = (0..num_nodes as NodeId)
+            .into_par_iter()
+            .map(|node| {
+                let inflow: f64 = reversed
+                    .neighbors(node)
+                    .map(|source| shares[source as usize])
+                    .sum();
+                let jump = jump[node as usize];
+                (1.0 - config.damping) * jump + config.damping * (inflow + dangling * jump)
+            })
+            .collect();
+
+        let change: f64 = next
+            .par_iter()
+            .zip(&scores)
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        scores = next;
+        if change < config.tolerance {
+            break;
+        }
+    }
+
+    scores
+}
+
+/// The `n` nodes with the highest scores, best first, leaving out nodes that scored 0
+pub fn top(scores: &[f64], n: usize) -> Vec<(NodeId, f64)> {
+    let mut ranked: Vec<_> = scores
+        .iter()
+        .enumerate()
+        .filter(|(_, &score)| score > 0.0)
+        .map(|(node, &score)| (node as NodeId, score))
+        .collect();
+    let best_first = |a: &(NodeId, f64), b: &(NodeId, f64)| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0));
+    if n < ranked.len() {
+        ranked.select_nth_unstable_by(n, best_first);
+        ranked.truncate(n);
+    }
+    ranked.sort_unstable_by(best_first);
+    ranked
+}
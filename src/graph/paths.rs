@@ -0,0 +1,198 @@
+//! Shortest link paths between two pages.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{Adjacency, NodeId};
+use crate::extractors::links::Page;
+use crate::wiki::normalize_title;
+
+/// Nodes and links a path search must avoid
+#[derive(Default)]
+pub struct Banned {
+    pub nodes: HashSet<NodeId>,
+    pub links: HashSet<(NodeId, NodeId)>,
+}
+
+/// Shortest path from `source` to `target`, as the list of nodes on it. `reversed` must be the
+/// reverse of `graph`.
+///
+/// Searches forward from the source and backward from the target at the same time, always
+/// growing the smaller frontier by a whole level, so only a small part of the graph is visited
+/// even between far apart pages.
+pub fn shortest_path(
+    graph: &impl Adjacency,
+    reversed: &impl Adjacency,
+    source: NodeId,
+    target: NodeId,
+    banned: &Banned,
+) -> Option<Vec<NodeId>> {
+    if banned.nodes.contains(&source) || banned.nodes.contains(&target) {
+        return None;
+    }
+    if source == target {
+        return Some(vec![source]);
+    }
+
+    // Node → (previous node towards the source / next node towards the target, distance)
+    let mut forward = HashMap::from([(source, (source, 0))]);
+    let mut backward = HashMap::from([(target, (target, 0))]);
+    let mut forward_frontier = vec![source];
+    let mut backward_frontier = vec![target];
+
+    while !forward_frontier.is_empty() && !backward_frontier.is_empty() {
+        let meeting = if forward_frontier.len() <= backward_frontier.len() {
+            expand_level(
+                graph,
+                &mut forward_frontier,
+                &mut forward,
+                &backward,
+                |node, neighbor| (node, neighbor),
+                banned,
+            )
+        } else {
+            expand_level(
+                reversed,
+                &mut backward_frontier,
+                &mut backward,
+                &forward,
+                |node, neighbor| (neighbor, node),
+                banned,
+            )
+        };
+
+        if let Some(meeting) = meeting {
+            return Some(join(&forward, &backward, meeting));
+        }
+    }
+
+    None
+}
+
+type SearchTree = HashMap<NodeId, (NodeId, u32)>;
+
+// Visit the next level of one side of the search. Returns the node where it meets the other side
+// with the shortest total length, if any. `link` turns a followed edge into the link it stands
+// for, so banned links are recognised from both sides.
+fn expand_level(
+    adjacency: &impl Adjacency,
+    frontier: &mut Vec<NodeId>,
+    visited: &mut SearchTree,
+    other: &SearchTree,
+    link: impl Fn(NodeId, NodeId) -> (NodeId, NodeId),
+    banned: &Banned,
+) -> Option<NodeId> {
+    let mut next = Vec::new();
+    let mut best: Option<(u32, NodeId)> = None;
+
+    for &node in frontier.iter() {
+        let distance = visited[&node].1;
+        for neighbor in adjacency.neighbors(node) {
+            if visited.contains_key(&neighbor)
+                || banned.nodes.contains(&neighbor)
+                || banned.links.contains(&link(node, neighbor))
+            {
+                continue;
+            }
+            visited.insert(neighbor, (node, distance + 1));
+            next.push(neighbor);
+
+            if let Some(&(_, other_distance)) = other.get(&neighbor) {
+                let length = distance + 1 + other_distance;
+                if best.is_none_or(|(best_length, _)| length < best_length) {
+                    best = Some((length, neighbor));
+                }
+            }
+        }
+    }
+
+    *frontier = next;
+    best.map(|(_, meeting)| meeting)
+}
+
+// Path through `meeting`, following the search trees back to the source and the target
+fn join(forward: &SearchTree, backward: &SearchTree, meeting: NodeId) -> Vec<NodeId> {
+    let mut path = vec![meeting];
+    let mut node = meeting;
+    while forward[&node].1 > 0 {
+        node = forward[&node].0;
+        path.push(node);
+    }
+    path.reverse();
+
+    let mut node = meeting;
+    while backward[&node].1 > 0 {
+        node = backward[&node].0;
+        path.push(node);
+    }
+    path
+}
+
+/// Up to `k` shortest paths from `source` to `target` without repeated nodes, shortest first,
+/// using Yen's algorithm
+pub fn k_shortest_paths(
+    graph: &impl Adjacency,
+    reversed: &impl Adjacency,
+    source: NodeId,
+    target: NodeId,
+    k: usize,
+) -> Vec<Vec<NodeId>> {
+    let mut paths: Vec<Vec<NodeId>> = Vec::new();
+    match shortest_path(graph, reversed, source, target, &Banned::default()) {
+        Some(path) => paths.push(path),
+        None => return paths,
+    }
+    let mut candidates: Vec<Vec<NodeId>> = Vec::new();
+
+    while paths.len() < k {
+        let last = paths.last().unwrap().clone();
+
+        // Deviate from the last path at every node in turn
+        for i in 0..last.len() - 1 {
+            let spur = last[i];
+            let root = &last[..=i];
+
+            let mut banned = Banned::default();
+            for path in &paths {
+                if path.len() > i + 1 && &path[..=i] == root {
+                    banned.links.insert((path[i], path[i + 1]));
+                }
+            }
+            banned.nodes.extend(&root[..i]);
+
+            if let Some(spur_path) = shortest_path(graph, reversed, spur, target, &banned) {
+                let mut path = root[..i].to_vec();
+                path.extend(spur_path);
+                if !paths.contains(&path) && !candidates.contains(&path) {
+                    candidates.push(path);
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            break;
+        }
+        // Shortest candidate, ties broken by node ids so results are stable
+        let best = (0..candidates.len())
+            .min_by(|&a, &b| {
+                (candidates[a].len(), &candidates[a]).cmp(&(candidates[b].len(), &candidates[b]))
+            })
+            .unwrap();
+        paths.push(candidates.swap_remove(best));
+    }
+
+    paths
+}
+
+/// Label of the first link on `page` that resolves to the page titled `target`, matching targets
+/// the same way as [super::adjacency_from_links]
+pub fn anchor_text<'a>(page: &'a Page, target: &str) -> Option<&'a str> {
+    page.links
+        .iter()
+        .find(|link| link.target == target)
+        .or_else(|| {
+            page.links
+                .iter()
+                .find(|link| normalize_title(&link.target) == target)
+        })
+        .map(|link| link.label.as_str())
+}
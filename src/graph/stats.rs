@@ -0,0 +1,287 @@
+//! Structural statistics of the link graph.
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::{Adjacency, CsrGraph, NodeId};
+
+#[derive(Serialize)]
+pub struct GraphStats<'a> {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Article links to pages that don't exist, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dangling_links: Option<u64>,
+    /// Pages that link nowhere
+    pub pages_without_links: usize,
+    /// Pages nothing links to
+    pub pages_without_backlinks: usize,
+    /// Pages without links either way
+    pub isolated_pages: usize,
+    /// Links from A to another page B where B also links to A
+    pub reciprocal_links: usize,
+    pub reciprocal_ratio: f64,
+    pub out_degree: DegreeDistribution,
+    pub in_degree: DegreeDistribution,
+    /// Pages with the most links
+    pub top_linking: Vec<Hub<'a>>,
+    /// Pages with the most backlinks
+    pub top_linked: Vec<Hub<'a>>,
+    pub weakly_connected_components: Components,
+    pub strongly_connected_components: Components,
+}
+
+#[derive(Serialize)]
+pub struct DegreeDistribution {
+    pub mean: f64,
+    pub median: usize,
+    pub p90: usize,
+    pub p99: usize,
+    pub max: usize,
+    /// Pages by degree, in buckets of 0, 1, 2-3, 4-7, ..
+    pub histogram: Vec<DegreeBucket>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct DegreeBucket {
+    pub min: usize,
+    pub max: usize,
+    pub pages: usize,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Hub<'a> {
+    pub title: &'a str,
+    pub degree: usize,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct Components {
+    pub count: usize,
+    /// Pages in the largest component
+    pub largest: usize,
+    /// Components of a single page
+    pub singletons: usize,
+}
+
+impl<'a> GraphStats<'a> {
+    /// Statistics of `graph`, listing `top` hubs. `reversed` must be `graph.reversed()`.
+    pub fn compute(graph: &'a CsrGraph, reversed: &CsrGraph, top: usize) -> Self {
+        let out_degrees: Vec<_> = graph.nodes().map(|node| graph.degree(node)).collect();
+        let in_degrees: Vec<_> = reversed.nodes().map(|node| reversed.degree(node)).collect();
+
+        let reciprocal_links: usize = (0..graph.num_nodes() as NodeId)
+            .into_par_iter()
+            .map(|node| {
+                // Lists of the reversed graph are sorted, as it's built in node order
+                let linking: Vec<_> = reversed.neighbors(node).collect();
+                graph
+                    .neighbors(node)
+                    .filter(|&target| target != node && linking.binary_search(&target).is_ok())
+                    .count()
+            })
+            .sum();
+
+        Self {
+            nodes: graph.num_nodes(),
+            edges: graph.num_edges(),
+            dangling_links: None,
+            pages_without_links: out_degrees.iter().filter(|&&d| d == 0).count(),
+            pages_without_backlinks: in_degrees.iter().filter(|&&d| d == 0).count(),
+            isolated_pages: out_degrees
+                .iter()
+                .zip(&in_degrees)
+                .filter(|&(&out, &inbound)| out == 0 && inbound == 0)
+                .count(),
+            reciprocal_links,
+            reciprocal_ratio: ratio(reciprocal_links, graph.num_edges()),
+            out_degree: DegreeDistribution::new(&out_degrees),
+            in_degree: DegreeDistribution::new(&in_degrees),
+            top_linking: hubs(graph, &out_degrees, top),
+            top_linked: hubs(graph, &in_degrees, top),
+            weakly_connected_components: Components::new(&weakly_connected_components(graph)),
+            strongly_connected_components: Components::new(&strongly_connected_components(graph)),
+        }
+    }
+}
+
+impl DegreeDistribution {
+    fn new(degrees: &[usize]) -> Self {
+        let mut sorted = degrees.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: f64| {
+            let i = ((sorted.len() as f64 * p) as usize).min(sorted.len().saturating_sub(1));
+            sorted.get(i).copied().unwrap_or(0)
+        };
+
+        let mut histogram: Vec<DegreeBucket> = Vec::new();
+        for &degree in &sorted {
+            let (min, max) = match degree {
+                0 => (0, 0),
+                _ => {
+                    let min = 1 << degree.ilog2();
+                    (min, 2 * min - 1)
+                }
+            };
+            match histogram.last_mut() {
+                Some(bucket) if bucket.min == min => bucket.pages += 1,
+                _ => histogram.push(DegreeBucket { min, max, pages: 1 }),
+            }
+        }
+
+        Self {
+            mean: ratio(sorted.iter().sum(), sorted.len()),
+            median: percentile(0.5),
+            p90: percentile(0.9),
+            p99: percentile(0.99),
+            max: sorted.last().copied().unwrap_or(0),
+            histogram,
+        }
+    }
+}
+
+impl Components {
+    fn new(components: &[u32]) -> Self {
+        let mut sizes = vec![0usize; components.len()];
+        for &component in components {
+            sizes[component as usize] += 1;
+        }
+        sizes.retain(|&size| size > 0);
+
+        Self {
+            count: sizes.len(),
+            largest: sizes.iter().copied().max().unwrap_or(0),
+            singletons: sizes.iter().filter(|&&size| size == 1).count(),
+        }
+    }
+}
+
+fn ratio(a: usize, b: usize) -> f64 {
+    if b == 0 {
+        0.0
+    } else {
+        a as f64 / b as f64
+    }
+}
+
+fn hubs<'a>(graph: &'a CsrGraph, degrees: &[usize], top: usize) -> Vec<Hub<'a>> {
+    let mut nodes: Vec<_> = graph.nodes().collect();
+    nodes.sort_unstable_by(|&a, &b| {
+        degrees[b as usize]
+            .cmp(&degrees[a as usize])
+            .then(a.cmp(&b))
+    });
+    nodes
+        .into_iter()
+        .take(top)
+        .map(|node| Hub {
+            title: graph.title(node),
+            degree: degrees[node as usize],
+        })
+        .collect()
+}
+
+/// Component of every node when links are followed both ways, numbered from 0 in no particular
+/// order
+pub fn weakly_connected_components(graph: &CsrGraph) -> Vec<u32> {
+    let mut parents: Vec<NodeId> = graph.nodes().collect();
+    for node in graph.nodes() {
+        for target in graph.neighbors(node) {
+            let (a, b) = (find(&mut parents, node), find(&mut parents, target));
+            if a != b {
+                parents[a.max(b) as usize] = a.min(b);
+            }
+        }
+    }
+
+    let mut ids = vec![u32::MAX; graph.num_nodes()];
+    let mut next = 0;
+    (0..graph.num_nodes() as NodeId)
+        .map(|node| {
+            let root = find(&mut parents, node) as usize;
+            if ids[root] == u32::MAX {
+                ids[root] = next;
+                next += 1;
+            }
+            ids[root]
+        })
+        .collect()
+}
+
+// Root of the union-find tree of `node`, halving the path to it
+fn find(parents: &mut [NodeId], mut node: NodeId) -> NodeId {
+    while parents[node as usize] != node {
+        let grandparent = parents[parents[node as usize] as usize];
+        parents[node as usize] = grandparent;
+        node = grandparent;
+    }
+    node
+}
+
+/// Strongly connected component of every node, numbered from 0 in no particular order, with
+/// Tarjan's algorithm. The depth first search keeps its own stack, so long chains of links don't
+/// overflow the thread's stack.
+pub fn strongly_connected_components(graph: &impl Adjacency) -> Vec<u32> {
+    const UNVISITED: u32 = u32::MAX;
+
+    let num_nodes = graph.num_nodes();
+    let mut index = vec![UNVISITED; num_nodes];
+    let mut lowlink = vec![0; num_nodes];
+    let mut on_stack = vec![false; num_nodes];
+    let mut components = vec![UNVISITED; num_nodes];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut next_component = 0;
+
+    for root in 0..num_nodes as NodeId {
+        if index[root as usize] != UNVISITED {
+            continue;
+        }
+
+        let mut calls = vec![(root, graph.neighbors(root))];
+        index[root as usize] = next_index;
+        lowlink[root as usize] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root as usize] = true;
+
+        while let Some((node, neighbors)) = calls.last_mut() {
+            let node = *node;
+            match neighbors.next() {
+                Some(target) if index[target as usize] == UNVISITED => {
+                    index[target as usize] = next_index;
+                    lowlink[target as usize] = next_index;
+                    next_index += 1;
+                    stack.push(target);
+                    on_stack[target as usize] = true;
+                    calls.push((target, graph.neighbors(target)));
+                }
+                Some(target) => {
+                    if on_stack[target as usize] {
+                        lowlink[node as usize] = lowlink[node as usize].min(index[target as usize]);
+                    }
+                }
+                None => {
+                    calls.pop();
+                    if let Some((parent, _)) = calls.last() {
+                        lowlink[*parent as usize] =
+                            lowlink[*parent as usize].min(lowlink[node as usize]);
+                    }
+                    if lowlink[node as usize] == index[node as usize] {
+                        loop {
+                            let member = stack.pop().expect("node is on the stack");
+                            on_stack[member as usize] = false;
+                            components[member as usize] = next_component;
+                            if member == node {
+                                break;
+                            }
+                        }
+                        next_component += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    components
+}
@@ -0,0 +1,327 @@
+//! Subgraphs around a root page.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, VecDeque},
+};
+
+use clap::ValueEnum;
+use rayon::prelude::*;
+
+use super::{Adjacency, NodeId};
+
+/// A node of a subgraph, with the number of hops on the shortest path from the root
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Reached {
+    pub node: NodeId,
+    pub distance: u32,
+}
+
+/// A node of a subgraph grown from several seeds, with the seeds that reached it (as indices into
+/// the seeds) and the distance from the closest one
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Seeded {
+    pub node: NodeId,
+    pub distance: u32,
+    pub seeds: Vec<usize>,
+}
+
+/// How to join the subgraphs grown from several seeds
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Combine {
+    /// Nodes reached from any seed
+    #[default]
+    Union,
+    /// Nodes reached from every seed
+    Intersection,
+}
+
+const UNREACHED: u32 = u32::MAX;
+
+/// Every node at most `max_depth` hops from `root`, including `root` itself, in breadth first
+/// order
+pub fn depth_limited(graph: &impl Adjacency, root: NodeId, max_depth: u32) -> Vec<Reached> {
+    let mut distances = vec![UNREACHED; graph.num_nodes()];
+    let mut reached = Vec::new();
+    let mut queue = VecDeque::new();
+
+    distances[root as usize] = 0;
+    queue.push_back(root);
+
+    while let Some(node) = queue.pop_front() {
+        let distance = distances[node as usize];
+        reached.push(Reached { node, distance });
+        if distance == max_depth {
+            continue;
+        }
+
+        for child in graph.neighbors(node) {
+            if distances[child as usize] == UNREACHED {
+                distances[child as usize] = distance + 1;
+                queue.push_back(child);
+            }
+        }
+    }
+
+    reached
+}
+
+/// Join the subgraphs grown from every seed, closest nodes first
+pub fn combine(per_seed: &[Vec<Reached>], combine: Combine) -> Vec<Seeded> {
+    let mut nodes: HashMap<NodeId, Seeded> = HashMap::new();
+    for (seed, reached) in per_seed.iter().enumerate() {
+        for r in reached {
+            let node = nodes.entry(r.node).or_insert_with(|| Seeded {
+                node: r.node,
+                distance: r.distance,
+                seeds: Vec::new(),
+            });
+            node.distance = node.distance.min(r.distance);
+            node.seeds.push(seed);
+        }
+    }
+
+    let mut combined: Vec<_> = nodes
+        .into_values()
+        .filter(|node| combine == Combine::Union || node.seeds.len() == per_seed.len())
+        .collect();
+    combined.sort_unstable_by_key(|node| (node.distance, node.node));
+    combined
+}
+
+/// Start with a budget of `depth` at the root. Following a link from a page costs
+/// (number of links on the page) / `fanout_factor`, and pages are only expanded while the budget
+/// left is above 0, so the subgraph goes deeper through pages with few links.
+///
+/// Every node gets the cheapest cost over all paths from the root, and its distance is the
+/// number of hops on that path. Nodes are returned in order of increasing cost.
+pub fn depth_limited_with_fanout_factor(
+    graph: &impl Adjacency,
+    root: NodeId,
+    depth: f64,
+    fanout_factor: f64,
+) -> Vec<Reached> {
+    expand(
+        graph,
+        root,
+        depth,
+        Some(fanout_factor),
+        &ExpansionPolicy::default(),
+    )
+}
+
+/// Titles of lists, indexes and pages about a year, decade, century or day, which link to
+/// everything that happened in them rather than to one topic
+pub const LIST_PAGE_PATTERNS: &[&str] = &[
+    r"^(Lists?|Index|Outline) of ",
+    r"^\d{1,4}( BC| AD)?$",
+    r"^\d{1,3}0s( BC)?$",
+    r"^\d{1,2}(st|nd|rd|th) (century|millennium)( BC)?$",
+    r"^\d{1,4} in ",
+    r"^(January|February|March|April|May|June|July|August|September|October|November|December) \d{1,2}$",
+];
+
+/// Limits on how far a subgraph grows through hubs, see [expand]
+#[derive(Default)]
+pub struct ExpansionPolicy {
+    /// Number of links to every page, needed by `max_in_degree` and `in_degree_weight`
+    pub in_degrees: Vec<u32>,
+    /// Pages with more links to them than this are kept, but their links aren't followed
+    pub max_in_degree: Option<u32>,
+    /// Entering a page costs this much more for every e-fold of links to it
+    pub in_degree_weight: f64,
+    /// Stop once the subgraph has this many pages
+    pub max_pages: Option<usize>,
+    /// Pages never entered, by node. Empty when none are.
+    pub excluded: Vec<bool>,
+}
+
+impl ExpansionPolicy {
+    /// Whether the policy changes anything compared to an unlimited expansion
+    pub fn is_limiting(&self) -> bool {
+        self.max_in_degree.is_some()
+            || self.in_degree_weight != 0.0
+            || self.max_pages.is_some()
+            || !self.excluded.is_empty()
+    }
+
+    fn in_degree(&self, node: NodeId) -> u32 {
+        self.in_degrees.get(node as usize).copied().unwrap_or(0)
+    }
+
+    fn is_excluded(&self, node: NodeId) -> bool {
+        self.excluded.get(node as usize).copied().unwrap_or(false)
+    }
+}
+
+/// Grow a subgraph from `root`, cheapest pages first, while the cost of a page is below `depth`.
+///
+/// Following a link from a page costs (number of links on the page) / `fanout_factor`, or 1 without
+/// a fanout factor, which limits the subgraph to pages at most `depth` links away. On top of that,
+/// `policy` can make pages with many links to them (hubs like countries) more expensive to enter,
+/// stop at them, skip pages altogether, and stop at a number of pages, keeping the cheapest. The
+/// root is always kept and expanded.
+///
+/// Every node gets the cheapest cost over all paths from the root, and its distance is the
+/// number of hops on that path. Nodes are returned in order of increasing cost.
+pub fn expand(
+    graph: &impl Adjacency,
+    root: NodeId,
+    depth: f64,
+    fanout_factor: Option<f64>,
+    policy: &ExpansionPolicy,
+) -> Vec<Reached> {
+    let mut costs = vec![f64::INFINITY; graph.num_nodes()];
+    let mut distances = vec![UNREACHED; graph.num_nodes()];
+    let mut done = vec![false; graph.num_nodes()];
+    let mut reached = Vec::new();
+    let mut heap = BinaryHeap::new();
+
+    costs[root as usize] = 0.0;
+    distances[root as usize] = 0;
+    heap.push(Candidate {
+        cost: 0.0,
+        node: root,
+    });
+
+    while let Some(Candidate { cost, node }) = heap.pop() {
+        if done[node as usize] {
+            continue;
+        }
+        done[node as usize] = true;
+        let distance = distances[node as usize];
+        reached.push(Reached { node, distance });
+
+        if policy
+            .max_pages
+            .is_some_and(|max_pages| reached.len() >= max_pages)
+        {
+            break;
+        }
+        if cost >= depth {
+            continue;
+        }
+        if node != root
+            && policy
+                .max_in_degree
+                .is_some_and(|max_in_degree| policy.in_degree(node) > max_in_degree)
+        {
+            continue;
+        }
+
+        let link_cost = match fanout_factor {
+            Some(fanout_factor) => graph.degree(node) as f64 / fanout_factor,
+            None => 1.0,
+        };
+        for child in graph.neighbors(node) {
+            if policy.is_excluded(child) {
+                continue;
+            }
+            let mut child_cost = cost + link_cost;
+            if policy.in_degree_weight != 0.0 {
+                child_cost += policy.in_degree_weight * (policy.in_degree(child) as f64).ln_1p();
+            }
+
+            let child_idx = child as usize;
+            if child_cost < costs[child_idx] {
+                costs[child_idx] = child_cost;
+                distances[child_idx] = distance + 1;
+                heap.push(Candidate {
+                    cost: child_cost,
+                    node: child,
+                });
+            }
+        }
+    }
+
+    reached
+}
+
+pub struct RandomWalkConfig {
+    pub walks: usize,
+    /// Links followed by every walk
+    pub walk_length: usize,
+    /// Chance of jumping back to the root instead of following a link, at every step
+    pub restart_probability: f64,
+    pub seed: u64,
+}
+
+// Walks simulated with one generator, so results don't depend on the number of threads
+const WALKS_PER_CHUNK: usize = 1000;
+
+/// Run random walks from the `roots` in turn and keep the nodes visited at least `min_visits`
+/// times, with their number of visits, most visited first. Walks jump back to their root from
+/// pages without links, so they stay around the root however many links its neighbours have.
+pub fn random_walk(
+    graph: &(impl Adjacency + Sync),
+    roots: &[NodeId],
+    config: &RandomWalkConfig,
+    min_visits: u32,
+) -> Vec<(NodeId, u32)> {
+    let num_chunks = config.walks.div_ceil(WALKS_PER_CHUNK);
+    let visits = (0..num_chunks)
+        .into_par_iter()
+        .map(|chunk| {
+            let mut rng = fastrand::Rng::with_seed(config.seed.wrapping_add(chunk as u64));
+            let walks = WALKS_PER_CHUNK.min(config.walks - chunk * WALKS_PER_CHUNK);
+            let mut visits: HashMap<NodeId, u32> = HashMap::new();
+
+            for walk in 0..walks {
+                let root = roots[(chunk * WALKS_PER_CHUNK + walk) % roots.len()];
+                let mut node = root;
+                *visits.entry(node).or_default() += 1;
+                for _ in 0..config.walk_length {
+                    let degree = graph.degree(node);
+                    node = if degree == 0 || rng.f64() < config.restart_probability {
+                        root
+                    } else {
+                        graph.neighbors(node).nth(rng.usize(..degree)).unwrap()
+                    };
+                    *visits.entry(node).or_default() += 1;
+                }
+            }
+            visits
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (node, count) in b {
+                *a.entry(node).or_default() += count;
+            }
+            a
+        });
+
+    let mut visited: Vec<_> = visits
+        .into_iter()
+        .filter(|&(_, count)| count >= min_visits)
+        .collect();
+    visited.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    visited
+}
+
+// Heap entry ordered so that the lowest cost pops first
+struct Candidate {
+    cost: f64,
+    node: NodeId,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .cost
+            .total_cmp(&self.cost)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
@@ -0,0 +1,725 @@
+use super::communities::{self, Community, LabelPropagationConfig, Projection};
+use super::export::{self, Format, Subgraph};
+use super::pagerank::{self, PageRankConfig};
+use super::paths::{self, Banned};
+use super::stats::{self, Components, DegreeBucket, GraphStats, Hub};
+use super::subgraph::{self, Combine, ExpansionPolicy, RandomWalkConfig, Reached, Seeded};
+use super::{
+    open_reversed, page_targets, reversed_path, write, write_file, Adjacency, CsrGraph, Directed,
+    Direction, NodeId,
+};
+use crate::extractors::links::{Link, LinkKind, Page};
+use regex::RegexSet;
+
+fn graph() -> CsrGraph {
+    let titles = ["Paris", "France", "Émile Zola", "Berlin"];
+    let adjacency = vec![vec![1], vec![0, 2], vec![0, 1, 3], vec![]];
+    CsrGraph::build(&titles, &adjacency)
+}
+
+#[test]
+fn queries() {
+    let graph = graph();
+    assert_eq!(graph.num_nodes(), 4);
+    assert_eq!(graph.num_edges(), 6);
+
+    assert_eq!(graph.neighbors(2).collect::<Vec<_>>(), vec![0, 1, 3]);
+    assert_eq!(graph.neighbors(3).count(), 0);
+    assert_eq!(graph.degree(1), 2);
+
+    assert_eq!(graph.title(2), "Émile Zola");
+    for node in graph.nodes() {
+        assert_eq!(graph.node(graph.title(node)), Some(node));
+    }
+    assert_eq!(graph.node("London"), None);
+}
+
+#[test]
+fn file_round_trip() {
+    let titles = ["A", "B", "C"];
+    let adjacency = vec![vec![1, 2], vec![2], vec![0]];
+    let file = tempfile::NamedTempFile::new().unwrap();
+    write(file.as_file(), &titles, &adjacency).unwrap();
+
+    let graph = CsrGraph::open(file.path()).unwrap();
+    let read: Vec<Vec<_>> = graph
+        .nodes()
+        .map(|n| graph.neighbors(n).collect())
+        .collect();
+    assert_eq!(read, adjacency);
+    assert_eq!(graph.node("C"), Some(2));
+}
+
+#[test]
+fn invalid_files() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), b"not a graph").unwrap();
+    assert!(CsrGraph::open(file.path()).is_err());
+
+    let mut bytes = Vec::new();
+    write(&mut bytes, &["A"], &[vec![0]]).unwrap();
+    bytes.pop();
+    std::fs::write(file.path(), bytes).unwrap();
+    assert!(CsrGraph::open(file.path()).is_err());
+}
+
+// 0 → 1 → 2 → 3, plus a shortcut 0 → 3 that a depth first search could reach late
+fn chain() -> CsrGraph {
+    let titles = ["Root", "A", "B", "C", "Hub", "D"];
+    let adjacency = vec![
+        vec![1, 4, 3],
+        vec![2],
+        vec![3],
+        vec![5],
+        vec![0, 1, 2, 3, 5],
+        vec![],
+    ];
+    CsrGraph::build(&titles, &adjacency)
+}
+
+fn distances(reached: &[Reached]) -> Vec<(NodeId, u32)> {
+    let mut distances: Vec<_> = reached.iter().map(|r| (r.node, r.distance)).collect();
+    distances.sort_unstable();
+    distances
+}
+
+#[test]
+fn depth_limited() {
+    let graph = chain();
+    let reached = subgraph::depth_limited(&graph, 0, 1);
+    assert_eq!(
+        reached[0],
+        Reached {
+            node: 0,
+            distance: 0
+        }
+    );
+    assert_eq!(distances(&reached), vec![(0, 0), (1, 1), (3, 1), (4, 1)]);
+
+    let reached = subgraph::depth_limited(&graph, 0, 2);
+    assert_eq!(
+        distances(&reached),
+        vec![(0, 0), (1, 1), (2, 2), (3, 1), (4, 1), (5, 2)]
+    );
+
+    let reached = subgraph::depth_limited(&graph, 1, 0);
+    assert_eq!(distances(&reached), vec![(1, 0)]);
+}
+
+#[test]
+fn depth_limited_with_fanout_factor() {
+    let graph = chain();
+    // Following a link from the root costs 3 / 3 = 1, from the hub 5 / 3, from a chain page 1 / 3
+    let reached = subgraph::depth_limited_with_fanout_factor(&graph, 0, 1.5, 3.0);
+    assert_eq!(
+        reached[0],
+        Reached {
+            node: 0,
+            distance: 0
+        }
+    );
+    // B costs 1 + 1/3 through A, C is cheapest straight from the root, D costs 1 + 1/3 through C.
+    // Paths through the hub cost 1 + 5/3, so they're never the cheapest.
+    assert_eq!(
+        distances(&reached),
+        vec![(0, 0), (1, 1), (2, 2), (3, 1), (4, 1), (5, 2)]
+    );
+
+    let reached = subgraph::depth_limited_with_fanout_factor(&graph, 0, 1.0, 3.0);
+    assert_eq!(distances(&reached), vec![(0, 0), (1, 1), (3, 1), (4, 1)]);
+}
+
+fn hubs() -> CsrGraph {
+    let titles = [
+        "Root",
+        "France",
+        "Paris",
+        "Lyon",
+        "List of cities",
+        "Émile Zola",
+    ];
+    let adjacency = vec![
+        vec![1, 5, 4],
+        vec![2, 3],
+        vec![1],
+        vec![1],
+        vec![3],
+        vec![2, 1],
+    ];
+    CsrGraph::build(&titles, &adjacency)
+}
+
+#[test]
+fn expansion_policy() {
+    let graph = hubs();
+    let unlimited = subgraph::expand(&graph, 0, 2.0, None, &ExpansionPolicy::default());
+    assert_eq!(
+        distances(&unlimited),
+        distances(&subgraph::depth_limited(&graph, 0, 2))
+    );
+
+    // Lyon is still reached through France
+    let mut excluded = vec![false; graph.num_nodes()];
+    excluded[4] = true;
+    let policy = ExpansionPolicy {
+        excluded,
+        ..Default::default()
+    };
+    let reached = subgraph::expand(&graph, 0, 2.0, None, &policy);
+    assert_eq!(
+        distances(&reached),
+        vec![(0, 0), (1, 1), (2, 2), (3, 2), (5, 1)]
+    );
+
+    // France is kept, but only Paris is reached, through Zola
+    let policy = ExpansionPolicy {
+        in_degrees: graph.in_degrees(),
+        max_in_degree: Some(3),
+        ..policy
+    };
+    let reached = subgraph::expand(&graph, 0, 2.0, None, &policy);
+    assert_eq!(distances(&reached), vec![(0, 0), (1, 1), (2, 2), (5, 1)]);
+    // The root is expanded whatever its in-degree
+    let reached = subgraph::expand(&graph, 1, 1.0, None, &policy);
+    assert_eq!(distances(&reached), vec![(1, 0), (2, 1), (3, 1)]);
+}
+
+#[test]
+fn in_degree_weight() {
+    let graph = hubs();
+    assert_eq!(graph.in_degrees(), vec![0, 4, 2, 2, 1, 1]);
+
+    // Entering France costs 1 + ln 5, Zola and the list 1 + ln 2
+    let policy = ExpansionPolicy {
+        in_degrees: graph.in_degrees(),
+        in_degree_weight: 1.0,
+        ..Default::default()
+    };
+    let reached = subgraph::expand(&graph, 0, 10.0, None, &policy);
+    let order: Vec<_> = reached.iter().map(|r| r.node).collect();
+    assert_eq!(order.len(), 6);
+    assert_eq!(order[0], 0);
+    assert!(order[1..3].contains(&4) && order[1..3].contains(&5));
+    assert_eq!(order[3], 1);
+    // Paris is cheapest through Zola, even though it's as many links away through France
+    assert_eq!(
+        reached.iter().find(|r| r.node == 2),
+        Some(&Reached {
+            node: 2,
+            distance: 2
+        })
+    );
+
+    let policy = ExpansionPolicy {
+        max_pages: Some(3),
+        ..policy
+    };
+    let reached = subgraph::expand(&graph, 0, 10.0, None, &policy);
+    assert_eq!(distances(&reached), vec![(0, 0), (4, 1), (5, 1)]);
+}
+
+#[test]
+fn list_page_patterns() {
+    let titles = [
+        "List of cities in France",
+        "Index of Paris-related articles",
+        "1999",
+        "1990s",
+        "19th century",
+        "2004 in film",
+        "March 3",
+        "Paris",
+        "1999 (Prince song)",
+        "Listening",
+    ];
+    let adjacency = vec![vec![]; titles.len()];
+    let graph = CsrGraph::build(&titles, &adjacency);
+    let patterns = RegexSet::new(subgraph::LIST_PAGE_PATTERNS).unwrap();
+    assert_eq!(
+        graph.titles_matching(&patterns),
+        vec![true, true, true, true, true, true, true, false, false, false]
+    );
+}
+
+#[test]
+fn reversed() {
+    let graph = chain();
+    let reversed = graph.reversed();
+    assert_eq!(reversed.num_edges(), graph.num_edges());
+    assert_eq!(reversed.neighbors(3).collect::<Vec<_>>(), vec![0, 2, 4]);
+    assert_eq!(reversed.neighbors(4).collect::<Vec<_>>(), vec![0]);
+    assert_eq!(reversed.node("Hub"), Some(4));
+}
+
+#[test]
+fn reversed_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let filename = dir.path().join("graph").to_str().unwrap().to_owned();
+    let titles = ["A", "B", "C"];
+    write_file(&filename, &titles, &[vec![1, 2], vec![2], vec![]]).unwrap();
+    let graph = CsrGraph::open(&filename).unwrap();
+    assert!(open_reversed(&filename, &graph).unwrap().is_none());
+
+    graph.write_reversed_file(&filename).unwrap();
+    let reversed = open_reversed(&filename, &graph).unwrap().unwrap();
+    assert_eq!(reversed.neighbors(2).collect::<Vec<_>>(), vec![0, 1]);
+    assert_eq!(reversed.node("B"), Some(1));
+
+    // A reversed file that doesn't belong to the graph is ignored
+    write_file(
+        &reversed_path(&filename),
+        &titles,
+        &[vec![], vec![], vec![]],
+    )
+    .unwrap();
+    assert!(open_reversed(&filename, &graph).unwrap().is_none());
+}
+
+#[test]
+fn directions() {
+    let graph = chain();
+    let reversed = graph.reversed();
+
+    let inbound = Directed::new(&graph, Some(&reversed), Direction::Inbound);
+    let reached = subgraph::depth_limited(&inbound, 5, 1);
+    assert_eq!(distances(&reached), vec![(3, 1), (4, 1), (5, 0)]);
+
+    let undirected = Directed::new(&graph, Some(&reversed), Direction::Undirected);
+    assert_eq!(undirected.degree(1), 3);
+    let reached = subgraph::depth_limited(&undirected, 5, 1);
+    assert_eq!(distances(&reached), vec![(3, 1), (4, 1), (5, 0)]);
+    let reached = subgraph::depth_limited(&undirected, 2, 1);
+    assert_eq!(distances(&reached), vec![(1, 1), (2, 0), (3, 1), (4, 1)]);
+
+    let outbound = Directed::new(&graph, None, Direction::Outbound);
+    assert_eq!(subgraph::depth_limited(&outbound, 5, 3).len(), 1);
+}
+
+#[test]
+fn shortest_path() {
+    let graph = chain();
+    let reversed = graph.reversed();
+    let none = Banned::default();
+
+    assert_eq!(
+        paths::shortest_path(&graph, &reversed, 0, 5, &none),
+        Some(vec![0, 3, 5])
+    );
+    assert_eq!(
+        paths::shortest_path(&graph, &reversed, 1, 5, &none),
+        Some(vec![1, 2, 3, 5])
+    );
+    assert_eq!(
+        paths::shortest_path(&graph, &reversed, 2, 2, &none),
+        Some(vec![2])
+    );
+    assert_eq!(paths::shortest_path(&graph, &reversed, 5, 0, &none), None);
+
+    let banned = Banned {
+        nodes: [3].into(),
+        links: [(0, 4)].into(),
+    };
+    assert_eq!(paths::shortest_path(&graph, &reversed, 0, 5, &banned), None);
+    let banned = Banned {
+        nodes: [4].into(),
+        links: [(0, 3)].into(),
+    };
+    assert_eq!(
+        paths::shortest_path(&graph, &reversed, 0, 5, &banned),
+        Some(vec![0, 1, 2, 3, 5])
+    );
+}
+
+#[test]
+fn k_shortest_paths() {
+    let graph = chain();
+    let reversed = graph.reversed();
+
+    assert_eq!(
+        paths::k_shortest_paths(&graph, &reversed, 0, 5, 3),
+        vec![vec![0, 3, 5], vec![0, 4, 5], vec![0, 4, 3, 5]]
+    );
+    // Every path without repeated pages
+    let all = paths::k_shortest_paths(&graph, &reversed, 0, 5, 100);
+    assert_eq!(all.len(), 6);
+    assert!(all.windows(2).all(|pair| pair[0].len() <= pair[1].len()));
+
+    assert!(paths::k_shortest_paths(&graph, &reversed, 5, 0, 3).is_empty());
+}
+
+#[test]
+fn only_article_links_are_resolved() {
+    let link = |target: &str, kind: LinkKind| Link {
+        target: target.to_owned(),
+        label: target.to_owned(),
+        kind,
+        template: None,
+        parameter: None,
+    };
+    let page = Page {
+        title: "Paris".to_owned(),
+        links: vec![
+            link("France", LinkKind::Article),
+            link("Category:France", LinkKind::Category),
+            link("fr:Paris", LinkKind::Interlanguage),
+            link("File:Paris.jpg", LinkKind::File),
+            link("france", LinkKind::Article),
+            link("Berlin", LinkKind::Article),
+            link("Atlantis", LinkKind::Article),
+            link("Category:Atlantis", LinkKind::Category),
+        ],
+    };
+    // Pages that share the title of a non-article link
+    let index_map = [
+        ("France", 0),
+        ("Category:France", 1),
+        ("fr:Paris", 2),
+        ("File:Paris.jpg", 3),
+        ("Berlin", 4),
+    ]
+    .into_iter()
+    .collect();
+
+    assert_eq!(page_targets(&page, &index_map), (vec![0, 4], 1));
+}
+
+#[test]
+fn anchor_text() {
+    let link = |target: &str, label: &str| Link {
+        target: target.to_owned(),
+        label: label.to_owned(),
+        kind: LinkKind::Article,
+        template: None,
+        parameter: None,
+    };
+    let page = Page {
+        title: "Paris".to_owned(),
+        links: vec![
+            link("france", "French"),
+            link("Émile_Zola#Life", "Zola"),
+            link("France", "France"),
+        ],
+    };
+
+    assert_eq!(paths::anchor_text(&page, "France"), Some("France"));
+    assert_eq!(paths::anchor_text(&page, "Émile Zola"), Some("Zola"));
+    assert_eq!(paths::anchor_text(&page, "Berlin"), None);
+}
+
+#[test]
+fn pagerank() {
+    let config = PageRankConfig::default();
+
+    let cycle = CsrGraph::build(&["A", "B", "C"], &[vec![1], vec![2], vec![0]]);
+    let scores = pagerank::pagerank(&cycle, &cycle.reversed(), &[], &config);
+    assert!(scores.iter().all(|score| (score - 1.0 / 3.0).abs() < 1e-9));
+
+    let graph = chain();
+    let reversed = graph.reversed();
+    let scores = pagerank::pagerank(&graph, &reversed, &[], &config);
+    assert!((scores.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    // C is linked from three pages, and D gets most of what C has
+    let ranked: Vec<_> = pagerank::top(&scores, 2)
+        .into_iter()
+        .map(|(node, _)| node)
+        .collect();
+    assert_eq!(ranked, vec![5, 3]);
+}
+
+#[test]
+fn personalized_pagerank() {
+    let config = PageRankConfig::default();
+    let graph = graph();
+    let reversed = graph.reversed();
+
+    // Berlin has no links, so every walk from it jumps back
+    let scores = pagerank::pagerank(&graph, &reversed, &[3], &config);
+    assert_eq!(pagerank::top(&scores, 10), vec![(3, 1.0)]);
+
+    let scores = pagerank::pagerank(&graph, &reversed, &[0], &config);
+    assert!((scores.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+    let ranked: Vec<_> = pagerank::top(&scores, 10)
+        .into_iter()
+        .map(|(node, _)| node)
+        .collect();
+    assert_eq!(ranked, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn random_walk() {
+    let graph = chain();
+    let config = RandomWalkConfig {
+        walks: 2500,
+        walk_length: 4,
+        restart_probability: 0.2,
+        seed: 7,
+    };
+
+    let visited = subgraph::random_walk(&graph, &[1], &config, 1);
+    let total: u32 = visited.iter().map(|&(_, visits)| visits).sum();
+    assert_eq!(total, 2500 * 5);
+    assert_eq!(visited[0].0, 1);
+    // Walks from A never reach the root or the hub
+    let mut nodes: Vec<_> = visited.iter().map(|&(node, _)| node).collect();
+    nodes.sort_unstable();
+    assert_eq!(nodes, vec![1, 2, 3, 5]);
+    assert_eq!(subgraph::random_walk(&graph, &[1], &config, 1), visited);
+
+    let rare = visited.last().unwrap().1;
+    assert_eq!(
+        subgraph::random_walk(&graph, &[1], &config, rare + 1).len(),
+        visited.len() - 1
+    );
+
+    let config = RandomWalkConfig {
+        restart_probability: 1.0,
+        ..config
+    };
+    assert_eq!(
+        subgraph::random_walk(&graph, &[0], &config, 1),
+        vec![(0, 12500)]
+    );
+}
+
+#[test]
+fn combine() {
+    let graph = chain();
+    // The chain below A, and the pages linking to D
+    let reversed = graph.reversed();
+    let per_seed = vec![
+        subgraph::depth_limited(&graph, 1, 2),
+        subgraph::depth_limited(&reversed, 5, 1),
+    ];
+
+    let union = subgraph::combine(&per_seed, Combine::Union);
+    let seeded = |node, distance, seeds: &[usize]| Seeded {
+        node,
+        distance,
+        seeds: seeds.to_vec(),
+    };
+    assert_eq!(
+        union,
+        vec![
+            seeded(1, 0, &[0]),
+            seeded(5, 0, &[1]),
+            seeded(2, 1, &[0]),
+            seeded(3, 1, &[0, 1]),
+            seeded(4, 1, &[1]),
+        ]
+    );
+
+    let intersection = subgraph::combine(&per_seed, Combine::Intersection);
+    assert_eq!(intersection, vec![seeded(3, 1, &[0, 1])]);
+}
+
+#[test]
+fn export() {
+    let titles = ["Tom & Jerry", "\"Heroes\"", "Cartoon", "Unrelated"];
+    let adjacency = vec![vec![2, 3], vec![0], vec![0, 1], vec![2]];
+    let graph = CsrGraph::build(&titles, &adjacency);
+    let node = |node, depth| export::Node {
+        node,
+        depth: Some(depth),
+        score: None,
+    };
+    let subgraph = Subgraph::induced(&graph, [node(0, 0), node(2, 1), node(1, 2), node(0, 3)]);
+    assert_eq!(subgraph.num_nodes(), 3);
+    assert_eq!(subgraph.num_edges(), 4);
+
+    let write = |format| {
+        let mut out = Vec::new();
+        subgraph.write(format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    assert_eq!(
+        write(Format::Tsv),
+        "Tom & Jerry\tCartoon\nCartoon\tTom & Jerry\nCartoon\t\"Heroes\"\n\"Heroes\"\tTom & Jerry\n"
+    );
+
+    let graphml = write(Format::Graphml);
+    assert!(graphml.contains(r#"<data key="label">Tom &amp; Jerry</data>"#));
+    assert!(graphml.contains(r#"<data key="depth">1</data>"#));
+    assert!(graphml.contains(r#"<edge source="n1" target="n2"/>"#));
+    assert!(!graphml.contains(r#"key="score">"#));
+
+    let gexf = write(Format::Gexf);
+    assert!(gexf.contains(r#"<node id="2" label="&quot;Heroes&quot;">"#));
+    assert!(gexf.contains(r#"<attvalue for="in_degree" value="2"/>"#));
+    assert!(gexf.contains(r#"<edge id="3" source="2" target="0"/>"#));
+
+    let dot = write(Format::Dot);
+    assert!(dot.contains(r#"n2 [label="\"Heroes\"", depth=2, in_degree=1, out_degree=1];"#));
+    assert!(dot.contains("n0 -> n1;"));
+    assert!(dot.ends_with("}\n"));
+}
+
+#[test]
+fn components() {
+    let graph = chain();
+    // The root and the hub link to each other, every other page is its own component
+    let scc = stats::strongly_connected_components(&graph);
+    assert_eq!(scc[0], scc[4]);
+    let mut ids = scc.clone();
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), 5);
+
+    let disconnected = CsrGraph::build(&["A", "B", "C", "D"], &[vec![1], vec![], vec![3], vec![2]]);
+    let wcc = stats::weakly_connected_components(&disconnected);
+    assert_eq!(wcc, vec![0, 0, 1, 1]);
+
+    // A cycle long enough to overflow a recursive search
+    let n: u32 = 300_000;
+    let titles: Vec<_> = (0..n).map(|i| i.to_string()).collect();
+    let adjacency: Vec<_> = (0..n).map(|i| vec![(i + 1) % n]).collect();
+    let cycle = CsrGraph::build(&titles, &adjacency);
+    assert!(stats::strongly_connected_components(&cycle)
+        .iter()
+        .all(|&component| component == 0));
+}
+
+#[test]
+fn graph_stats() {
+    let graph = chain();
+    let reversed = graph.reversed();
+    let stats = GraphStats::compute(&graph, &reversed, 2);
+
+    assert_eq!((stats.nodes, stats.edges), (6, 11));
+    assert_eq!(stats.pages_without_links, 1);
+    assert_eq!(stats.pages_without_backlinks, 0);
+    assert_eq!(stats.isolated_pages, 0);
+    // Root → Hub and Hub → Root
+    assert_eq!(stats.reciprocal_links, 2);
+    assert_eq!(
+        stats.top_linking,
+        vec![
+            Hub {
+                title: "Hub",
+                degree: 5
+            },
+            Hub {
+                title: "Root",
+                degree: 3
+            }
+        ]
+    );
+    assert_eq!(stats.top_linked[0].title, "C");
+    assert_eq!(stats.out_degree.max, 5);
+    assert_eq!(
+        stats.out_degree.histogram,
+        vec![
+            DegreeBucket {
+                min: 0,
+                max: 0,
+                pages: 1
+            },
+            DegreeBucket {
+                min: 1,
+                max: 1,
+                pages: 3
+            },
+            DegreeBucket {
+                min: 2,
+                max: 3,
+                pages: 1
+            },
+            DegreeBucket {
+                min: 4,
+                max: 7,
+                pages: 1
+            }
+        ]
+    );
+    assert_eq!(stats.weakly_connected_components.count, 1);
+    assert_eq!(
+        stats.strongly_connected_components,
+        Components {
+            count: 5,
+            largest: 2,
+            singletons: 4
+        }
+    );
+}
+
+#[test]
+fn projections() {
+    let graph = chain();
+    let reversed = graph.reversed();
+
+    let undirected = communities::project(&graph, &reversed, Projection::Undirected);
+    assert_eq!(undirected.num_edges(), 2 * 10);
+    assert_eq!(undirected.neighbors(0).collect::<Vec<_>>(), vec![1, 3, 4]);
+    assert_eq!(
+        undirected.neighbors(4).collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 5]
+    );
+
+    let reciprocal = communities::project(&graph, &reversed, Projection::Reciprocal);
+    assert_eq!(reciprocal.num_edges(), 2);
+    assert_eq!(reciprocal.neighbors(4).collect::<Vec<_>>(), vec![0]);
+}
+
+// Two groups of five pages linking to each other, and one link between the groups
+fn two_cliques() -> CsrGraph {
+    let titles: Vec<_> = (0..10).map(|i| format!("Page {}", i)).collect();
+    let mut adjacency: Vec<Vec<NodeId>> = (0..10)
+        .map(|i: NodeId| {
+            let group = i / 5 * 5;
+            (group..group + 5).filter(|&j| j != i).collect()
+        })
+        .collect();
+    adjacency[4].push(5);
+    CsrGraph::build(&titles, &adjacency)
+}
+
+#[test]
+fn label_propagation() {
+    let graph = two_cliques();
+    let undirected = communities::project(&graph, &graph.reversed(), Projection::Undirected);
+    // Results only depend on the seed on a single thread
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    let config = LabelPropagationConfig::default();
+
+    let labels = pool.install(|| communities::label_propagation(&undirected, &config));
+    assert!(labels[..5].iter().all(|&label| label == labels[0]));
+    assert!(labels[5..].iter().all(|&label| label == labels[5]));
+    assert_ne!(labels[0], labels[5]);
+    assert_eq!(
+        pool.install(|| communities::label_propagation(&undirected, &config)),
+        labels
+    );
+
+    // Pages without links stay on their own
+    let isolated = CsrGraph::build(&["A", "B"], &[vec![], vec![]]);
+    let mut labels = communities::label_propagation(&isolated, &config);
+    labels.sort_unstable();
+    assert_eq!(labels, vec![0, 1]);
+}
+
+#[test]
+fn summarize_communities() {
+    let graph = two_cliques();
+    let labels = [1, 1, 1, 0, 0, 0, 0, 0, 2, 1];
+    let backlinks: Vec<_> = (0..10).collect();
+
+    let summary = communities::summarize(&graph, &labels, &backlinks, 2, 2);
+    assert_eq!(
+        summary,
+        vec![
+            Community {
+                community: 0,
+                size: 5,
+                top_members: vec!["Page 7", "Page 6"]
+            },
+            Community {
+                community: 1,
+                size: 4,
+                top_members: vec!["Page 9", "Page 2"]
+            },
+        ]
+    );
+}
@@ -0,0 +1,95 @@
+//! Join the interlanguage links of several language editions into groups of articles about the
+//! same subject.
+//!
+//! Every (language, title) pair is a node and every interlanguage link an edge. Links are often
+//! one-sided or missing in some editions, so groups are the connected components rather than
+//! only the links of a single edition.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::extractors::interlanguage::Page;
+use crate::wiki::normalize_title;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Default)]
+pub struct Alignment {
+    ids: HashMap<(String, String), usize>,
+    nodes: Vec<(String, String)>,
+    // Union-find parent of every node
+    parent: Vec<usize>,
+}
+
+/// Titles of one subject in each language. A language has several titles when editions disagree.
+pub type AlignedGroup<'a> = BTreeMap<&'a str, Vec<&'a str>>;
+
+impl Alignment {
+    /// Add a page of the `language` edition and its interlanguage links
+    pub fn add_page(&mut self, language: &str, page: &Page) {
+        let node = self.node(language, &normalize_title(&page.title));
+        for link in &page.links {
+            let other = self.node(&link.language, &link.title);
+            self.union(node, other);
+        }
+    }
+
+    /// Groups of articles spanning at least `min_languages` languages, ordered by the title in
+    /// the first language of the group
+    pub fn groups(&mut self, min_languages: usize) -> Vec<AlignedGroup<'_>> {
+        let roots: Vec<_> = (0..self.nodes.len()).map(|node| self.find(node)).collect();
+
+        let mut groups: HashMap<usize, AlignedGroup> = HashMap::new();
+        for (node, root) in roots.into_iter().enumerate() {
+            let (language, title) = &self.nodes[node];
+            groups
+                .entry(root)
+                .or_default()
+                .entry(language)
+                .or_default()
+                .push(title);
+        }
+
+        let mut groups: Vec<_> = groups
+            .into_values()
+            .filter(|group| group.len() >= min_languages)
+            .map(|mut group| {
+                for titles in group.values_mut() {
+                    titles.sort_unstable();
+                }
+                group
+            })
+            .collect();
+        groups.sort();
+        groups
+    }
+
+    fn node(&mut self, language: &str, title: &str) -> usize {
+        let key = (language.to_lowercase(), title.to_owned());
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.ids.insert(key.clone(), id);
+        self.nodes.push(key);
+        self.parent.push(id);
+        id
+    }
+
+    fn find(&mut self, mut node: usize) -> usize {
+        while self.parent[node] != node {
+            // Path halving
+            self.parent[node] = self.parent[self.parent[node]];
+            node = self.parent[node];
+        }
+        node
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a != b {
+            self.parent[a.max(b)] = a.min(b);
+        }
+    }
+}
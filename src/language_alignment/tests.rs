@@ -0,0 +1,49 @@
+use super::Alignment;
+use crate::extractors::interlanguage::{InterlanguageLink, Page};
+
+fn page(title: &str, links: &[(&str, &str)]) -> Page {
+    Page {
+        title: title.to_owned(),
+        links: links
+            .iter()
+            .map(|(language, title)| InterlanguageLink {
+                language: language.to_string(),
+                title: title.to_string(),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn groups() {
+    let mut alignment = Alignment::default();
+    alignment.add_page("en", &page("Paris", &[("de", "Paris"), ("fr", "Paris")]));
+    alignment.add_page("en", &page("Lyon", &[]));
+    alignment.add_page("en", &page("Berlin", &[("de", "Berlin")]));
+    // Only the French edition links to the Spanish article
+    alignment.add_page("fr", &page("Paris", &[("es", "París")]));
+    // The German edition disagrees about Berlin
+    alignment.add_page("de", &page("Berlin (Stadt)", &[("en", "Berlin")]));
+
+    let groups = alignment.groups(2);
+    let groups: Vec<Vec<(&str, Vec<&str>)>> = groups
+        .into_iter()
+        .map(|group| group.into_iter().collect())
+        .collect();
+    assert_eq!(
+        groups,
+        vec![
+            vec![
+                ("de", vec!["Berlin", "Berlin (Stadt)"]),
+                ("en", vec!["Berlin"])
+            ],
+            vec![
+                ("de", vec!["Paris"]),
+                ("en", vec!["Paris"]),
+                ("es", vec!["París"]),
+                ("fr", vec!["Paris"]),
+            ],
+        ]
+    );
+    assert_eq!(alignment.groups(1).len(), 3);
+}
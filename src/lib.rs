@@ -1,7 +1,15 @@
+mod byte_channel;
 pub mod par_file;
-mod work_queue;
+pub mod work_queue;
 pub mod xml_parser;
 
+pub mod anchors;
+pub mod category_graph;
+pub mod dataset;
 pub mod extractors;
+pub mod graph;
+pub mod language_alignment;
+pub mod metrics;
+pub mod page_guard;
 pub mod progress;
 pub mod wiki;
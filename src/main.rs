@@ -3,11 +3,19 @@ use clap::{Parser, ValueEnum};
 use std::error::Error;
 use std::fs::File;
 use std::io::BufReader;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 use wikipedia_parser::extractors;
-use wikipedia_parser::par_file::ParFile;
-use wikipedia_parser::xml_parser::XMLParser;
+use wikipedia_parser::extractors::links::LinkKind;
+use wikipedia_parser::metrics::{self, Metrics};
+use wikipedia_parser::page_guard::{LimitAction, PageGuard};
+use wikipedia_parser::par_file::{self, ParFile};
+use wikipedia_parser::progress::ProgressFormat;
+use wikipedia_parser::wiki;
+use wikipedia_parser::work_queue::{WorkQueue, WorkQueueConfig};
+use wikipedia_parser::xml_parser::{DisambiguationPages, PageFilter, XMLParser};
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -20,30 +28,161 @@ struct Args {
     /// Path to the index file to write to
     #[arg(long)]
     output_index_file: String,
-    /// Number of threads to use for reading the input file
-    #[arg(long, default_value_t = 16u64)]
-    input_file_threads: u64,
+    /// Number of threads to use for reading the input file.
+    /// Calibrated from measured throughput if not given.
+    #[arg(long)]
+    input_file_threads: Option<u64>,
+    /// Size in MB of the blocks read from the input file.
+    /// Calibrated from measured throughput if not given.
+    #[arg(long)]
+    input_block_size_mb: Option<u64>,
+    /// Time in seconds to spend calibrating the input file reader
+    #[arg(long, default_value_t = 10.0)]
+    calibration_secs: f64,
     /// The extractor to run
     #[arg(short, long)]
     extractor: Extractor,
+    /// How to report progress
+    #[arg(long, value_enum, default_value_t)]
+    progress: ProgressFormat,
+    /// Seconds between progress lines when using `--progress log`
+    #[arg(long, default_value_t = 30.0)]
+    progress_interval_secs: f64,
+    /// Write the events of `--progress json` to this file instead of stderr
+    #[arg(long)]
+    progress_file: Option<PathBuf>,
+    /// Write pipeline metrics in the Prometheus text format to this file while running
+    #[arg(long)]
+    metrics_file: Option<String>,
+    /// Seconds between rewrites of the metrics file
+    #[arg(long, default_value_t = 15.0)]
+    metrics_interval_secs: f64,
+    /// Memory in MB for wikitext waiting to be processed
+    #[arg(long, default_value_t = 256)]
+    parser_queue_mb: usize,
+    /// Memory in MB for extractor output waiting to be written
+    #[arg(long, default_value_t = 256)]
+    writer_queue_mb: usize,
+    /// Size in MB of the buffer in front of the output data file
+    #[arg(long, default_value_t = 64)]
+    output_buffer_mb: usize,
+    /// Size in MB of the buffer in front of the output index file
+    #[arg(long, default_value_t = 16)]
+    index_buffer_mb: usize,
+    /// Pages with more wikitext than this many MB exceed the page limits
+    #[arg(long)]
+    max_page_mb: Option<f64>,
+    /// Pages that take longer than this many seconds to extract exceed the page limits
+    #[arg(long, default_value_t = 60.0)]
+    page_timeout_secs: f64,
+    /// What to do with pages that exceed the page limits
+    #[arg(long, value_enum, default_value_t)]
+    on_page_limit: LimitAction,
+    /// Record pages that exceed the page limits in this JSON lines file
+    #[arg(long)]
+    page_limit_report_file: Option<String>,
+    /// Comma separated kinds of links to output. All kinds if not given.
+    /// Used by the links and link-contexts extractors.
+    #[arg(long, value_delimiter = ',')]
+    link_kinds: Vec<LinkKind>,
+    /// Comma separated kinds of links to leave out of the output.
+    /// Used by the links and link-contexts extractors.
+    #[arg(long, value_delimiter = ',')]
+    exclude_link_kinds: Vec<LinkKind>,
+    /// Language code of the wiki the dump is from. Links prefixed with it, like `[[en:Paris]]` on
+    /// the English Wikipedia, are links to the wiki itself.
+    #[arg(long, default_value = wiki::DEFAULT_LANGUAGE)]
+    language: String,
 }
 
+type ExtractorFn = Box<dyn Fn(&[u8], &str) -> String + Send + Sync>;
+
 #[derive(ValueEnum, Clone, Copy, Debug)]
 enum Extractor {
     // Extract links graph
     Links,
     // Extract contents
     Contents,
+    // Extract links with the section, paragraph and position they appear at in the contents
+    LinkContexts,
+    // Extract links to other websites, with their domain and section
+    ExternalLinks,
+    // Extract the categories of articles, and the parents of categories
+    Categories,
+    // Extract links to the same article in other languages
+    Interlanguage,
+    // Extract the candidate articles listed on disambiguation pages
+    Disambiguation,
 }
 
-const PAR_FILE_BLOCK_SIZE: usize = 100 * 1024 * 1024;
-const PAR_FILE_QUEUE_SIZE: u64 = 1;
+impl Extractor {
+    fn name(self) -> &'static str {
+        match self {
+            Extractor::Links => "links",
+            Extractor::Contents => "contents",
+            Extractor::LinkContexts => "link-contexts",
+            Extractor::ExternalLinks => "external-links",
+            Extractor::Categories => "categories",
+            Extractor::Interlanguage => "interlanguage",
+            Extractor::Disambiguation => "disambiguation",
+        }
+    }
+
+    fn page_filter(self) -> PageFilter {
+        match self {
+            // Category pages hold the category hierarchy
+            Extractor::Categories => PageFilter::including("Category"),
+            Extractor::Disambiguation => PageFilter {
+                disambiguation: DisambiguationPages::Only,
+                ..PageFilter::default()
+            },
+            _ => PageFilter::default(),
+        }
+    }
+}
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
-    let extractor = match args.extractor {
-        Extractor::Links => extractors::links::extract,
-        Extractor::Contents => extractors::wikitext::extract,
+    wiki::set_language(&args.language);
+    let link_kinds = LinkKind::selection(&args.link_kinds, &args.exclude_link_kinds);
+    let links_extractor = || -> ExtractorFn {
+        let link_kinds = link_kinds.clone();
+        Box::new(move |input, title| extractors::links::extract_kinds(input, title, &link_kinds))
+    };
+    let link_contexts_extractor = || -> ExtractorFn {
+        let link_kinds = link_kinds.clone();
+        Box::new(move |input, title| {
+            extractors::wikitext::link_context::extract_kinds(input, title, &link_kinds)
+        })
+    };
+
+    let (extractor, fallback): (ExtractorFn, ExtractorFn) = match args.extractor {
+        // The links extractor is linear already, so it's its own fallback
+        Extractor::Links => (links_extractor(), links_extractor()),
+        Extractor::Contents => (
+            Box::new(extractors::wikitext::extract),
+            Box::new(extractors::wikitext::extract_fallback),
+        ),
+        Extractor::LinkContexts => (
+            link_contexts_extractor(),
+            Box::new(extractors::wikitext::link_context::extract_fallback),
+        ),
+        Extractor::ExternalLinks => (
+            Box::new(extractors::external_links::extract),
+            Box::new(extractors::external_links::extract),
+        ),
+        Extractor::Categories => (
+            Box::new(extractors::categories::extract),
+            Box::new(extractors::categories::extract),
+        ),
+        Extractor::Interlanguage => (
+            Box::new(extractors::interlanguage::extract),
+            Box::new(extractors::interlanguage::extract),
+        ),
+        Extractor::Disambiguation => (
+            Box::new(extractors::disambiguation::extract),
+            Box::new(extractors::disambiguation::extract),
+        ),
     };
 
     let input_filename = args.input_file;
@@ -51,14 +190,31 @@ fn main() -> Result<(), Box<dyn Error>> {
     let input_file = File::open(&input_filename)?;
     let input_file_size = input_file.metadata()?.len();
 
-    let input_par_file = ParFile::new(
-        input_filename,
-        PAR_FILE_BLOCK_SIZE as _,
-        PAR_FILE_QUEUE_SIZE,
+    let par_file_config = par_file::auto_config(
+        &input_filename,
         args.input_file_threads,
-    );
+        args.input_block_size_mb.map(|mb| mb * 1024 * 1024),
+        Duration::from_secs_f64(args.calibration_secs),
+    )?;
 
-    let input_file_reader = BufReader::with_capacity(PAR_FILE_BLOCK_SIZE, input_par_file);
+    let input_par_file = ParFile::with_config(input_filename, par_file_config);
+
+    let input_file_reader =
+        BufReader::with_capacity(par_file_config.block_size as _, input_par_file);
+
+    const M: usize = 1024 * 1024;
+    let work_queue_config = WorkQueueConfig {
+        parser_queue: args.parser_queue_mb * M,
+        writer_queue: args.writer_queue_mb * M,
+        data_buffer: args.output_buffer_mb * M,
+        index_buffer: args.index_buffer_mb * M,
+    };
+    let reader_budget =
+        par_file_config.buffered_bytes() as usize + par_file_config.block_size as usize;
+    println!(
+        "Memory budget: {} MB for buffers and queues, plus one page per worker thread",
+        (reader_budget + work_queue_config.budget()) / M
+    );
 
     let data_file = args.output_data_file;
     let index_file = args.output_index_file;
@@ -66,15 +222,52 @@ fn main() -> Result<(), Box<dyn Error>> {
     ensure_parent_folder_exists(&data_file);
     ensure_parent_folder_exists(&index_file);
 
-    let xml_parser = XMLParser::new(
+    let metrics = Arc::new(Metrics::new(args.extractor.name()));
+    if let Some(metrics_file) = &args.metrics_file {
+        metrics::spawn_file_exporter(
+            metrics.clone(),
+            metrics_file.clone(),
+            Duration::from_secs_f64(args.metrics_interval_secs),
+        );
+    }
+
+    if let Some(report_file) = &args.page_limit_report_file {
+        ensure_parent_folder_exists(report_file);
+    }
+    let page_guard = PageGuard::new(
+        args.max_page_mb.map(|mb| (mb * M as f64) as usize),
+        Some(Duration::from_secs_f64(args.page_timeout_secs)),
+        args.on_page_limit,
+        args.page_limit_report_file.as_deref(),
+    )?;
+
+    let work_queue = WorkQueue::new(
         data_file,
         index_file,
         extractor,
+        fallback,
+        page_guard,
+        metrics.clone(),
+        work_queue_config,
+    );
+    let xml_parser = XMLParser::new(
+        work_queue,
         input_file_reader,
         input_file_size,
-    )?;
+        args.progress.reporter(
+            Duration::from_secs_f64(args.progress_interval_secs),
+            args.progress_file.as_deref(),
+        )?,
+    )?
+    .with_page_filter(args.extractor.page_filter());
     xml_parser.parse_xml()?;
 
+    println!();
+    print!("{}", metrics.summary());
+    if let Some(metrics_file) = &args.metrics_file {
+        metrics.write_prometheus_file(metrics_file)?;
+    }
+
     Ok(())
 }
 
@@ -82,6 +275,6 @@ fn ensure_parent_folder_exists(filename: &str) {
     let path = Path::new(filename);
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
-            .expect(&format!("Failed to create folder for: {}", filename));
+            .unwrap_or_else(|_| panic!("Failed to create folder for: {}", filename));
     }
 }
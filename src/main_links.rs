@@ -0,0 +1,485 @@
+use std::{
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use clap::{Args, Parser, Subcommand};
+use serde::{de::DeserializeOwned, Serialize};
+
+use wikipedia_parser::anchors::AnchorDictionary;
+use wikipedia_parser::category_graph::CategoryGraph;
+use wikipedia_parser::dataset::{self, read_index_file, PageIndex};
+use wikipedia_parser::extractors::{categories, interlanguage, links};
+use wikipedia_parser::graph::{
+    self,
+    communities::{self, LabelPropagationConfig, Projection},
+    pagerank,
+    stats::GraphStats,
+    CsrGraph,
+};
+use wikipedia_parser::language_alignment::Alignment;
+use wikipedia_parser::par_file::{self, ParFileConfig};
+use wikipedia_parser::progress::{ProgressFormat, ProgressReporter};
+
+/// Aggregations over the output of the links and categories extractors
+#[derive(Parser, Debug)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Build a dictionary of link labels and the articles they link to
+    AnchorDictionary {
+        #[command(flatten)]
+        input: InputArgs,
+        /// JSON lines file with every label and the distribution of its targets
+        #[arg(short, long)]
+        output_file: String,
+        /// JSON lines file with every target and the labels used to link to it
+        #[arg(long)]
+        output_targets_file: Option<String>,
+        /// Drop label → target pairs seen fewer times than this
+        #[arg(long, default_value_t = 1)]
+        min_count: u64,
+    },
+    /// Resolve the links into a compact graph file that the subgraph extractor loads instantly,
+    /// and its reversed graph into the same file name with `.reversed` appended
+    BuildCsr {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(short, long)]
+        output_file: String,
+    },
+    /// Report the size, degree distributions, hubs, reciprocity and connected components of the
+    /// link graph as JSON
+    GraphStats {
+        #[command(flatten)]
+        input: InputArgs,
+        #[arg(short, long)]
+        output_file: String,
+        /// Number of hubs to list
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+    /// Score every page by PageRank over a graph file written by `build-csr`
+    PageRank {
+        #[arg(long)]
+        input_graph_file: String,
+        /// `title<TAB>score` lines, highest score first
+        #[arg(short, long)]
+        output_file: String,
+        /// Compute the personalized PageRank of these pages instead, where random jumps land on
+        /// a seed page
+        #[arg(long = "seed")]
+        seeds: Vec<String>,
+        /// Only write the best pages
+        #[arg(long)]
+        top: Option<usize>,
+        #[arg(long, default_value_t = 0.85)]
+        damping: f64,
+        #[arg(long, default_value_t = 100)]
+        max_iterations: usize,
+        #[arg(long, default_value_t = 1e-9)]
+        tolerance: f64,
+    },
+    /// Group pages into communities of densely linked pages, by label propagation over a graph
+    /// file written by `build-csr`
+    Communities {
+        #[arg(long)]
+        input_graph_file: String,
+        /// `title<TAB>community` lines for every page, communities numbered from 0 by decreasing
+        /// size
+        #[arg(short, long)]
+        output_file: String,
+        /// JSON lines file with the size and best linked pages of every community
+        #[arg(long)]
+        summary_file: Option<String>,
+        #[arg(long, value_enum, default_value_t)]
+        projection: Projection,
+        #[arg(long, default_value_t = 20)]
+        max_iterations: usize,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+        /// Leave communities smaller than this out of the summary
+        #[arg(long, default_value_t = 2)]
+        min_size: usize,
+        /// Number of pages listed for every community in the summary
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Build the category hierarchy from the output of the categories extractor
+    CategoryGraph {
+        #[command(flatten)]
+        input: InputArgs,
+        /// JSON lines file with every category, its parents, subcategories and articles
+        #[arg(short, long)]
+        output_file: String,
+    },
+    /// Join the output of the interlanguage extractor for several languages into groups of
+    /// articles about the same subject
+    AlignLanguages {
+        /// `LANGUAGE=FOLDER` for every language edition, where FOLDER holds the `data.jsonl` and
+        /// `index.txt` of its interlanguage extractor run
+        #[arg(
+            long = "run",
+            value_name = "LANGUAGE=FOLDER",
+            required = true,
+            value_parser = parse_run
+        )]
+        runs: Vec<(String, String)>,
+        #[command(flatten)]
+        reader: ReaderArgs,
+        /// JSON lines file with one object per group, mapping language codes to titles
+        #[arg(short, long)]
+        output_file: String,
+        /// Only write groups with articles in at least this many languages
+        #[arg(long, default_value_t = 2)]
+        min_languages: usize,
+    },
+}
+
+/// Where to read the extracted data from
+#[derive(Args, Debug)]
+struct InputArgs {
+    #[arg(long)]
+    input_data_file: String,
+    #[arg(long)]
+    input_index_file: String,
+    #[command(flatten)]
+    reader: ReaderArgs,
+}
+
+/// How to read extracted data
+#[derive(Args, Debug)]
+struct ReaderArgs {
+    /// Calibrated from measured throughput if not given
+    #[arg(long)]
+    input_file_threads: Option<u64>,
+    /// Calibrated from measured throughput if not given
+    #[arg(long)]
+    input_block_size_mb: Option<u64>,
+    #[arg(long, default_value_t = 10.0)]
+    calibration_secs: f64,
+    /// How to report progress
+    #[arg(long, value_enum, default_value_t)]
+    progress: ProgressFormat,
+    /// Seconds between progress lines when using `--progress log`
+    #[arg(long, default_value_t = 30.0)]
+    progress_interval_secs: f64,
+    /// Write the events of `--progress json` to this file instead of stderr
+    #[arg(long)]
+    progress_file: Option<PathBuf>,
+}
+
+impl InputArgs {
+    /// Fold every page of the input, see [dataset::fold_pages]
+    fn fold_pages<P: DeserializeOwned, A: Send>(
+        &self,
+        identity: impl Fn() -> A + Send + Sync,
+        fold: impl Fn(A, &PageIndex, P) -> A + Send + Sync,
+        reduce: impl Fn(A, A) -> A + Send + Sync,
+    ) -> std::io::Result<A> {
+        self.reader.fold_pages(
+            &self.input_data_file,
+            &self.input_index_file,
+            identity,
+            fold,
+            reduce,
+        )
+    }
+}
+
+impl ReaderArgs {
+    fn progress_reporter(&self) -> std::io::Result<Box<dyn ProgressReporter>> {
+        self.progress.reporter(
+            Duration::from_secs_f64(self.progress_interval_secs),
+            self.progress_file.as_deref(),
+        )
+    }
+
+    fn par_file_config(&self, data_file: &str) -> std::io::Result<ParFileConfig> {
+        par_file::auto_config(
+            data_file,
+            self.input_file_threads,
+            self.input_block_size_mb.map(|mb| mb * 1024 * 1024),
+            Duration::from_secs_f64(self.calibration_secs),
+        )
+    }
+
+    fn fold_pages<P: DeserializeOwned, A: Send>(
+        &self,
+        data_file: &str,
+        index_file: &str,
+        identity: impl Fn() -> A + Send + Sync,
+        fold: impl Fn(A, &PageIndex, P) -> A + Send + Sync,
+        reduce: impl Fn(A, A) -> A + Send + Sync,
+    ) -> std::io::Result<A> {
+        let par_file_config = self.par_file_config(data_file)?;
+        let index_list = read_index_file(index_file, par_file_config);
+
+        Ok(dataset::fold_pages(
+            data_file,
+            &index_list,
+            par_file_config,
+            self.progress_reporter()?,
+            identity,
+            fold,
+            reduce,
+        ))
+    }
+}
+
+fn parse_run(run: &str) -> Result<(String, String), String> {
+    match run.split_once('=') {
+        Some((language, folder)) if !language.is_empty() && !folder.is_empty() => {
+            Ok((language.to_lowercase(), folder.to_owned()))
+        }
+        _ => Err(format!("expected LANGUAGE=FOLDER, got `{}`", run)),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::AnchorDictionary {
+            input,
+            output_file,
+            output_targets_file,
+            min_count,
+        } => {
+            let mut dictionary = input.fold_pages(
+                AnchorDictionary::default,
+                |mut dictionary, _, page: links::Page| {
+                    dictionary.add_page(&page);
+                    dictionary
+                },
+                |mut a, b| {
+                    a.merge(b);
+                    a
+                },
+            )?;
+            dictionary.prune(min_count);
+            println!("{} labels", dictionary.len());
+
+            write_json_lines(&output_file, dictionary.labels())?;
+            if let Some(output_targets_file) = output_targets_file {
+                write_json_lines(&output_targets_file, dictionary.targets())?;
+            }
+        }
+        Command::BuildCsr { input, output_file } => {
+            let par_file_config = input.reader.par_file_config(&input.input_data_file)?;
+            let index_list = read_index_file(&input.input_index_file, par_file_config);
+            let adjacency = graph::adjacency_from_links(
+                &input.input_data_file,
+                &index_list,
+                par_file_config,
+                input.reader.progress_reporter()?,
+            );
+
+            let titles: Vec<_> = index_list.iter().map(|page| page.title.as_str()).collect();
+            graph::write_file(&output_file, &titles, &adjacency)?;
+            let num_edges: usize = adjacency.iter().map(Vec::len).sum();
+            println!("{} nodes, {} edges", titles.len(), num_edges);
+            drop(adjacency);
+
+            // Saves reversing the graph on every query that follows links backwards
+            println!("Reversing graph ..");
+            CsrGraph::open(&output_file)?.write_reversed_file(&output_file)?;
+        }
+        Command::GraphStats {
+            input,
+            output_file,
+            top,
+        } => {
+            let par_file_config = input.reader.par_file_config(&input.input_data_file)?;
+            let index_list = read_index_file(&input.input_index_file, par_file_config);
+            let resolved = graph::resolve_links(
+                &input.input_data_file,
+                &index_list,
+                par_file_config,
+                input.reader.progress_reporter()?,
+            );
+            let titles: Vec<_> = index_list.iter().map(|page| page.title.as_str()).collect();
+            let graph = CsrGraph::build(&titles, &resolved.adjacency);
+            drop(resolved.adjacency);
+
+            println!("Computing statistics ..");
+            let reversed = graph.reversed();
+            let mut stats = GraphStats::compute(&graph, &reversed, top);
+            stats.dangling_links = Some(resolved.dangling_links);
+            println!(
+                "{} nodes, {} edges, largest strongly connected component of {} pages",
+                stats.nodes, stats.edges, stats.strongly_connected_components.largest
+            );
+
+            if let Some(parent) = Path::new(&output_file).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut writer = BufWriter::new(File::create(&output_file)?);
+            serde_json::to_writer_pretty(&mut writer, &stats)?;
+            writer.write_all(b"\n")?;
+            writer.flush()?;
+        }
+        Command::PageRank {
+            input_graph_file,
+            output_file,
+            seeds,
+            top,
+            damping,
+            max_iterations,
+            tolerance,
+        } => {
+            let graph = CsrGraph::open(&input_graph_file)?;
+            let seeds = seeds
+                .iter()
+                .map(|title| {
+                    graph
+                        .node(title)
+                        .ok_or_else(|| format!("seed page not found: {}", title))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let reversed = reversed_graph(&input_graph_file, &graph)?;
+            let config = pagerank::PageRankConfig {
+                damping,
+                max_iterations,
+                tolerance,
+            };
+            let scores = pagerank::pagerank(&graph, &reversed, &seeds, &config);
+
+            let ranked = pagerank::top(&scores, top.unwrap_or(usize::MAX));
+            println!("{} pages scored", ranked.len());
+            let lines: Vec<_> = ranked
+                .iter()
+                .map(|&(node, score)| format!("{}\t{}", graph.title(node), score))
+                .collect();
+            dataset::write_lines(lines.iter().map(String::as_str), &output_file);
+        }
+        Command::Communities {
+            input_graph_file,
+            output_file,
+            summary_file,
+            projection,
+            max_iterations,
+            seed,
+            min_size,
+            top,
+        } => {
+            let graph = CsrGraph::open(&input_graph_file)?;
+            let reversed = reversed_graph(&input_graph_file, &graph)?;
+            let projected = communities::project(&graph, &reversed, projection);
+
+            println!("Propagating labels ..");
+            let config = LabelPropagationConfig {
+                max_iterations,
+                seed,
+                ..Default::default()
+            };
+            let labels = communities::label_propagation(&projected, &config);
+            let num_communities = labels.iter().max().map_or(0, |&max| max + 1);
+            println!("{} communities", num_communities);
+
+            let lines: Vec<_> = graph
+                .nodes()
+                .map(|node| format!("{}\t{}", graph.title(node), labels[node as usize]))
+                .collect();
+            dataset::write_lines(lines.iter().map(String::as_str), &output_file);
+
+            if let Some(summary_file) = summary_file {
+                // The most linked pages best represent a community
+                let backlinks: Vec<_> =
+                    reversed.nodes().map(|node| reversed.degree(node)).collect();
+                let summary = communities::summarize(&graph, &labels, &backlinks, min_size, top);
+                write_json_lines(&summary_file, summary)?;
+            }
+        }
+        Command::CategoryGraph { input, output_file } => {
+            let graph = input.fold_pages(
+                CategoryGraph::default,
+                |mut graph, _, page: categories::Page| {
+                    graph.add_page(page);
+                    graph
+                },
+                |mut a, b| {
+                    a.merge(b);
+                    a
+                },
+            )?;
+            let entries = graph.entries();
+            println!("{} categories", entries.len());
+
+            write_json_lines(&output_file, entries)?;
+        }
+        Command::AlignLanguages {
+            runs,
+            reader,
+            output_file,
+            min_languages,
+        } => {
+            let mut alignment = Alignment::default();
+            for (language, folder) in runs {
+                println!("Reading {} ..", language);
+                let folder = Path::new(&folder);
+                // Pages without links don't help aligning
+                let pages = reader.fold_pages(
+                    &folder.join("data.jsonl").to_string_lossy(),
+                    &folder.join("index.txt").to_string_lossy(),
+                    Vec::new,
+                    |mut pages, _, page: interlanguage::Page| {
+                        if !page.links.is_empty() {
+                            pages.push(page);
+                        }
+                        pages
+                    },
+                    |mut a, b| {
+                        a.extend(b);
+                        a
+                    },
+                )?;
+                for page in &pages {
+                    alignment.add_page(&language, page);
+                }
+            }
+
+            let groups = alignment.groups(min_languages);
+            println!("{} groups", groups.len());
+
+            write_json_lines(&output_file, groups)?;
+        }
+    }
+
+    Ok(())
+}
+
+// The reversed graph saved by `build-csr` next to the graph file, or the graph reversed in memory
+// for files written before it did that
+fn reversed_graph(graph_file: &str, graph: &CsrGraph) -> std::io::Result<CsrGraph> {
+    if let Some(reversed) = graph::open_reversed(graph_file, graph)? {
+        return Ok(reversed);
+    }
+    println!("Reversing graph ..");
+    Ok(graph.reversed())
+}
+
+fn write_json_lines<T: Serialize>(
+    filename: &str,
+    items: impl IntoIterator<Item = T>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = Path::new(filename).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut writer = BufWriter::new(File::create(filename)?);
+    for item in items {
+        serde_json::to_writer(&mut writer, &item)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
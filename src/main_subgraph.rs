@@ -1,310 +1,578 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::{BufReader, Read, Seek, SeekFrom},
-    path::Path,
-    time::{Duration, SystemTime},
-};
+use std::{collections::HashSet, fs::File, path::PathBuf, time::Duration};
 
 use clap::{Parser, ValueEnum};
-use rayon::prelude::*;
 
-use wikipedia_parser::extractors::links::Page;
-use wikipedia_parser::par_file::ParFile;
-use wikipedia_parser::progress::Progress;
+use rayon::prelude::*;
+use regex::RegexSet;
+
+use wikipedia_parser::category_graph::CategoryGraph;
+use wikipedia_parser::dataset::{read_index_file, read_page, write_lines, PageIndex};
+use wikipedia_parser::extractors::links;
+use wikipedia_parser::graph::{
+    self, export, pagerank, paths,
+    subgraph::{self, Combine, ExpansionPolicy, LIST_PAGE_PATTERNS},
+    CsrGraph, Directed, Direction, NodeId,
+};
+use wikipedia_parser::par_file;
+use wikipedia_parser::progress::ProgressFormat;
 
 #[derive(Parser, Debug)]
 struct Args {
+    /// Graph file written by `links build-csr`. Loads instantly, so prefer it to the data and
+    /// index files, which are parsed into a graph on every run. With both, the data and index
+    /// files are only read for the anchor texts of paths.
     #[arg(long)]
-    input_data_file: String,
+    input_graph_file: Option<String>,
+    #[arg(long, requires = "input_index_file")]
+    input_data_file: Option<String>,
+    #[arg(long, requires = "input_data_file")]
+    input_index_file: Option<String>,
+    /// Calibrated from measured throughput if not given
     #[arg(long)]
-    input_index_file: String,
+    input_file_threads: Option<u64>,
+    /// Calibrated from measured throughput if not given
     #[arg(long)]
-    input_file_threads: u64,
+    input_block_size_mb: Option<u64>,
+    #[arg(long, default_value_t = 10.0)]
+    calibration_secs: f64,
     #[arg(short, long)]
     output_file: String,
-    #[arg(short, long)]
-    root_page: String,
-    #[arg(short, long)]
+    /// Also write the pages with the links between them, for graph tools
+    #[arg(long)]
+    export_file: Option<String>,
+    #[arg(long, value_enum, default_value_t)]
+    export_format: export::Format,
+    /// Page to grow the subgraph from. Repeat it to grow the subgraph from several seed pages.
+    #[arg(short, long = "root-page")]
+    root_pages: Vec<String>,
+    /// File with more seed pages, one title per line
+    #[arg(long)]
+    seed_file: Option<String>,
+    /// Use the articles in this category as seed pages
+    #[arg(long, requires = "category_graph_file")]
+    seed_category: Option<String>,
+    /// Category graph written by `links category-graph`, to look up `seed-category` in
+    #[arg(long)]
+    category_graph_file: Option<String>,
+    /// Also use the articles in subcategories of `seed-category` down to this many levels
+    #[arg(long, default_value_t = 0)]
+    category_depth: usize,
+    /// How to join the subgraphs of several seed pages
+    #[arg(long, value_enum, default_value_t)]
+    combine: Combine,
+    #[arg(short, long, default_value_t = 1.0)]
     depth: f64,
     #[arg(short, long)]
     method: SubgraphMethod,
+    /// Which links to follow. Inbound and undirected traversals build the reversed graph first.
+    #[arg(long, value_enum, default_value_t)]
+    direction: Direction,
     #[arg(short, long)]
     fanout_factor: Option<f64>,
+    /// Don't follow the links of pages with more links to them than this, like countries. The
+    /// pages themselves are kept.
+    #[arg(long)]
+    max_in_degree: Option<u32>,
+    /// Make entering a page cost this much more for every e-fold of links to it, so the
+    /// depth limited methods reach hubs last
+    #[arg(long, default_value_t = 0.0)]
+    in_degree_weight: f64,
+    /// Stop the depth limited methods at this many pages, keeping the closest (cheapest) ones
+    #[arg(long)]
+    max_pages: Option<usize>,
+    /// Leave out lists, indexes, and year, decade, century and day pages
+    #[arg(long)]
+    exclude_list_pages: bool,
+    /// Leave out pages whose titles match this regular expression. Can be repeated.
+    #[arg(long = "exclude-pattern")]
+    exclude_patterns: Vec<String>,
+    /// Page the `path` method searches paths to, from the root page
+    #[arg(short, long, required_if_eq("method", "path"))]
+    target_page: Option<String>,
+    /// Number of shortest paths the `path` method finds
+    #[arg(short = 'k', long, default_value_t = 1)]
+    num_paths: usize,
+    /// Number of pages the `personalized-page-rank` method keeps
+    #[arg(long, default_value_t = 1000)]
+    top_pages: usize,
+    /// Probability of following a link rather than jumping back to the root, for the
+    /// `personalized-page-rank` method
+    #[arg(long, default_value_t = 0.85)]
+    damping: f64,
+    /// Number of walks the `random-walk` method runs from the root page
+    #[arg(long, default_value_t = 10_000)]
+    walks: usize,
+    /// Links followed by every walk of the `random-walk` method
+    #[arg(long, default_value_t = 20)]
+    walk_length: usize,
+    /// Chance of a walk jumping back to the root page at every step
+    #[arg(long, default_value_t = 0.15)]
+    restart_probability: f64,
+    /// Seed for the `random-walk` method. The same seed gives the same subgraph.
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Pages the `random-walk` method keeps must be visited at least this many times
+    #[arg(long, default_value_t = 2)]
+    min_visits: u32,
+    /// How to report progress
+    #[arg(long, value_enum, default_value_t)]
+    progress: ProgressFormat,
+    /// Seconds between progress lines when using `--progress log`
+    #[arg(long, default_value_t = 30.0)]
+    progress_interval_secs: f64,
+    /// Write the events of `--progress json` to this file instead of stderr
+    #[arg(long)]
+    progress_file: Option<PathBuf>,
+}
+
+struct Input {
+    graph: CsrGraph,
+    /// Index of the data file, when one is given
+    index_list: Option<Vec<PageIndex>>,
+}
+
+impl Args {
+    fn load(&self) -> std::io::Result<Input> {
+        let data = match (&self.input_data_file, &self.input_index_file) {
+            (Some(data_file), Some(index_file)) => {
+                let par_file_config = par_file::auto_config(
+                    data_file,
+                    self.input_file_threads,
+                    self.input_block_size_mb.map(|mb| mb * 1024 * 1024),
+                    Duration::from_secs_f64(self.calibration_secs),
+                )?;
+                let index_list = read_index_file(index_file, par_file_config);
+                Some((data_file, par_file_config, index_list))
+            }
+            _ => None,
+        };
+
+        if let Some(graph_file) = &self.input_graph_file {
+            return Ok(Input {
+                graph: CsrGraph::open(graph_file)?,
+                index_list: data.map(|(_, _, index_list)| index_list),
+            });
+        }
+
+        let Some((data_file, par_file_config, index_list)) = data else {
+            return Err(std::io::Error::other(
+                "either --input-graph-file or --input-data-file and --input-index-file are needed",
+            ));
+        };
+
+        let adjacency = graph::adjacency_from_links(
+            data_file,
+            &index_list,
+            par_file_config,
+            self.progress.reporter(
+                Duration::from_secs_f64(self.progress_interval_secs),
+                self.progress_file.as_deref(),
+            )?,
+        );
+        let titles: Vec<_> = index_list.iter().map(|page| page.title.as_str()).collect();
+        let graph = CsrGraph::build(&titles, &adjacency);
+        println!("Graph built");
+
+        Ok(Input {
+            graph,
+            index_list: Some(index_list),
+        })
+    }
+}
+
+impl Args {
+    /// How the depth limited methods treat hubs and excluded pages
+    fn expansion_policy(&self, graph: &CsrGraph) -> Result<ExpansionPolicy, regex::Error> {
+        let mut policy = ExpansionPolicy {
+            max_in_degree: self.max_in_degree,
+            in_degree_weight: self.in_degree_weight,
+            max_pages: self.max_pages,
+            ..Default::default()
+        };
+        if policy.max_in_degree.is_some() || policy.in_degree_weight != 0.0 {
+            policy.in_degrees = graph.in_degrees();
+        }
+
+        let mut patterns = self.exclude_patterns.clone();
+        if self.exclude_list_pages {
+            patterns.extend(LIST_PAGE_PATTERNS.iter().map(|pattern| pattern.to_string()));
+        }
+        if !patterns.is_empty() {
+            policy.excluded = graph.titles_matching(&RegexSet::new(&patterns)?);
+        }
+        Ok(policy)
+    }
+
+    /// Titles of the seed pages from all sources, without repeats
+    fn seed_titles(&self) -> std::io::Result<Vec<String>> {
+        let mut titles = self.root_pages.clone();
+        if let Some(seed_file) = &self.seed_file {
+            let seeds = std::fs::read_to_string(seed_file)?;
+            titles.extend(
+                seeds
+                    .lines()
+                    .map(str::trim)
+                    .filter(|title| !title.is_empty())
+                    .map(str::to_owned),
+            );
+        }
+        if let (Some(category), Some(category_graph_file)) =
+            (&self.seed_category, &self.category_graph_file)
+        {
+            let category_graph = CategoryGraph::open(category_graph_file)?;
+            let articles = category_graph.articles_under(category, self.category_depth);
+            println!("{} articles in {}", articles.len(), category);
+            titles.extend(articles.into_iter().map(str::to_owned));
+        }
+
+        let mut seen = HashSet::new();
+        titles.retain(|title| seen.insert(title.clone()));
+        Ok(titles)
+    }
 }
 
 #[derive(ValueEnum, Clone, Copy, Debug)]
 enum SubgraphMethod {
-    /// Get the links in a tree of fixed depth starting from the root page.
+    /// Get the pages at most `depth` links away from the root page.
     DepthLimited,
     /// Start with X=depth.
     /// Subtract (number of links in the page)/(fanout factor) from X when descending to
     /// a child link.
-    /// Stop when X <= 0.
+    /// Stop when X <= 0. Pages get the largest X over all paths from the root.
     DepthLimitedWithFanOutFactor,
+    /// List the pages that link to the root page, in alphabetical order.
+    WhatLinksHere,
+    /// Find the shortest paths of links from the root page to the target page, as in "six
+    /// degrees of Wikipedia". Anchor texts are included when the data and index files are given.
+    Path,
+    /// Keep the `top-pages` pages with the highest personalized PageRank from the root page: the
+    /// pages a random surfer who keeps returning to the root visits most.
+    PersonalizedPageRank,
+    /// Run random walks from the root page that jump back to it now and then, and keep the pages
+    /// visited at least `min-visits` times.
+    RandomWalk,
 }
 
 pub fn main() {
     let args = Args::parse();
 
-    let index_list = read_index_file(&args.input_index_file);
+    let Input { graph, index_list } = match args.load() {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
 
-    let index_map: HashMap<String, usize> = index_list
-        .iter()
-        .map(|i| (i.title.clone(), i.idx))
-        .collect();
-
-    let root_page_index = match index_map.get(&args.root_page) {
-        Some(x) => *x,
-        None => {
-            eprintln!("Error: Root page not found");
-            eprintln!("{}", &args.root_page);
+    let seed_titles = match args.seed_titles() {
+        Ok(titles) => titles,
+        Err(e) => {
+            eprintln!("Error: {}", e);
             return;
         }
     };
+    let (found, missing): (Vec<_>, Vec<_>) = seed_titles
+        .iter()
+        .map(|title| graph.node(title).ok_or(title))
+        .partition(Result::is_ok);
+    if !missing.is_empty() {
+        eprintln!("Error: Root page not found");
+        for title in missing.into_iter().filter_map(Result::err) {
+            eprintln!("{}", title);
+        }
+    }
+    let seeds: Vec<_> = found.into_iter().filter_map(Result::ok).collect();
+    if seeds.is_empty() {
+        eprintln!("Error: No root page given");
+        return;
+    }
+    let root_page_index = seeds[0];
+    let root_page = graph.title(root_page_index);
+    if seeds.len() > 1
+        && matches!(
+            args.method,
+            SubgraphMethod::WhatLinksHere | SubgraphMethod::Path
+        )
+    {
+        eprintln!("Error: This method takes a single root page");
+        return;
+    }
 
-    let graph = build_graph(
-        &args.input_data_file,
-        &index_list,
-        &index_map,
-        args.input_file_threads,
-    );
+    let needs_reversed = args.direction != Direction::Outbound
+        || matches!(
+            args.method,
+            SubgraphMethod::WhatLinksHere
+                | SubgraphMethod::Path
+                | SubgraphMethod::PersonalizedPageRank
+        );
+    let saved_reversed = match &args.input_graph_file {
+        Some(graph_file) if needs_reversed => graph::open_reversed(graph_file, &graph),
+        _ => Ok(None),
+    };
+    let saved_reversed = match saved_reversed {
+        Ok(saved_reversed) => saved_reversed,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let reversed = needs_reversed.then(|| {
+        saved_reversed.unwrap_or_else(|| {
+            println!("Reversing graph ..");
+            graph.reversed()
+        })
+    });
+    let directed = Directed::new(&graph, reversed.as_ref(), args.direction);
+    let policy = match args.expansion_policy(&graph) {
+        Ok(policy) => policy,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
 
-    let mut list = HashSet::new();
-    match args.method {
+    // Lines of the output file, and the pages to export
+    let (lines, nodes): (Vec<String>, Vec<export::Node>) = match args.method {
         SubgraphMethod::DepthLimited => {
             if args.fanout_factor.is_some() {
                 eprintln!("Ignoring the fanout factor argument.");
                 eprintln!("It's only used by the DepthLimitedWithFanOutFactor method");
             }
-            depth_limited_subgraph(
-                root_page_index,
-                &index_list,
-                &graph,
-                args.depth.trunc() as _,
-                &mut list,
-            );
+            let per_seed: Vec<_> = seeds
+                .par_iter()
+                .map(|&seed| {
+                    if policy.is_limiting() {
+                        subgraph::expand(&directed, seed, args.depth.trunc(), None, &policy)
+                    } else {
+                        subgraph::depth_limited(&directed, seed, args.depth.trunc() as _)
+                    }
+                })
+                .collect();
+            distance_output(&graph, &seeds, &per_seed, args.combine)
         }
         SubgraphMethod::DepthLimitedWithFanOutFactor => {
             let fanout_factor = match args.fanout_factor {
                 Some(x) => x,
                 None => {
                     eprintln!("Error: Fanout factor must be provided when using this method");
-                    eprintln!("{}", &args.root_page);
+                    eprintln!("{}", root_page);
                     return;
                 }
             };
-            depth_limited_subgraph_with_fanout_factor(
-                root_page_index,
-                &index_list,
+            let per_seed: Vec<_> = seeds
+                .par_iter()
+                .map(|&seed| {
+                    subgraph::expand(&directed, seed, args.depth, Some(fanout_factor), &policy)
+                })
+                .collect();
+            distance_output(&graph, &seeds, &per_seed, args.combine)
+        }
+        SubgraphMethod::WhatLinksHere => {
+            let reversed = reversed
+                .as_ref()
+                .expect("reversed graph is built for this method");
+            let mut linking: Vec<_> = reversed.neighbors(root_page_index).collect();
+            linking.sort_unstable_by_key(|&node| graph.title(node));
+            println!("{} pages link to {}", linking.len(), root_page);
+
+            let lines = linking
+                .iter()
+                .map(|&node| graph.title(node).to_owned())
+                .collect();
+            let nodes = std::iter::once((root_page_index, 0))
+                .chain(linking.iter().map(|&node| (node, 1)))
+                .map(|(node, depth)| export::Node {
+                    node,
+                    depth: Some(depth),
+                    score: None,
+                })
+                .collect();
+            (lines, nodes)
+        }
+        SubgraphMethod::Path => {
+            let target_page = args.target_page.as_deref().unwrap_or_default();
+            let Some(target_page_index) = graph.node(target_page) else {
+                eprintln!("Error: Target page not found");
+                eprintln!("{}", target_page);
+                return;
+            };
+            let reversed = reversed
+                .as_ref()
+                .expect("reversed graph is built for this method");
+            let found = paths::k_shortest_paths(
                 &graph,
-                args.depth,
-                fanout_factor,
-                &mut list,
+                reversed,
+                root_page_index,
+                target_page_index,
+                args.num_paths,
             );
-        }
-    }
-
-    write_lines(list.iter().map(String::as_str), &args.output_file);
-}
-
-fn build_graph(
-    filename: &str,
-    index_list: &[PageIndex],
-    index_map: &HashMap<String, usize>,
-    threads: u64,
-) -> HashMap<usize, Vec<usize>> {
-    let file = File::open(filename).unwrap();
-    let file_size = file.metadata().unwrap().len();
-    let mut i = 0;
-
-    let mut progress = Progress {
-        total: file_size,
-        rate_divider: 1024.0 * 1024.0,
-        rate_unit: "MB/s",
-        start: SystemTime::now(),
-        window_length: Duration::from_secs(5),
-        window_start: SystemTime::now(),
-        window_count: 0,
-    };
-
-    let mut file = ParFile::new(filename.to_owned(), 40 * 1024 * 1024, 1, threads);
-    let mut buf: Vec<u8> = Vec::new();
-
-    let graph = index_list
-        .iter()
-        .map(|page_index| {
-            i += 1;
-
-            if i >= 1_000 {
-                i = 0;
-                let s = progress.progress(page_index.start_offset, SystemTime::now());
-                print!("Progress: {}\r", s);
+            if found.is_empty() {
+                println!("No path from {} to {}", root_page, target_page);
             }
 
-            read_file_slice(
-                &mut file,
-                page_index.start_offset,
-                page_index.end_offset.unwrap_or(file_size),
-                &mut buf,
-            );
-
-            (page_index.idx, buf.clone())
-        })
-        .par_bridge()
-        .map(|(idx, buf)| {
-            let page: Page = serde_json::from_slice(&buf).unwrap();
+            let data_file = args.input_data_file.as_deref();
+            let lines: Vec<_> = found
+                .iter()
+                .map(|path| {
+                    let anchors = match (data_file, &index_list) {
+                        (Some(data_file), Some(index_list)) => {
+                            anchor_texts(&graph, data_file, index_list, path)
+                        }
+                        _ => vec![None; path.len() - 1],
+                    };
+                    format_path(&graph, path, &anchors)
+                })
+                .collect();
+            for line in &lines {
+                println!("{}", line);
+            }
 
-            let link_idxs: Vec<_> = page
-                .links
+            // Depth is the position on the shortest path through the page
+            let mut depths: Vec<(NodeId, u32)> = found
                 .iter()
-                .filter_map(|link| index_map.get(&link.target).map(|x| *x))
+                .flat_map(|path| path.iter().enumerate().map(|(i, &node)| (node, i as u32)))
                 .collect();
-            (idx, link_idxs)
-        })
-        .collect();
+            depths.sort_unstable_by_key(|&(node, depth)| (depth, node));
+            let nodes = depths
+                .into_iter()
+                .map(|(node, depth)| export::Node {
+                    node,
+                    depth: Some(depth),
+                    score: None,
+                })
+                .collect();
+            (lines, nodes)
+        }
+        SubgraphMethod::PersonalizedPageRank => {
+            // Scores flow along the followed links, so each page sums them over the opposite ones
+            let opposite = Directed::new(&graph, reversed.as_ref(), args.direction.reversed());
+            let config = pagerank::PageRankConfig {
+                damping: args.damping,
+                ..Default::default()
+            };
+            let scores = pagerank::pagerank(&directed, &opposite, &seeds, &config);
 
-    let s = progress.progress(file_size, SystemTime::now());
-    println!("Progress: {}", s);
-    println!();
-    println!("Graph built");
+            // One `title<TAB>score` line per page, highest score first
+            let top = pagerank::top(&scores, args.top_pages);
+            score_output(&graph, &top)
+        }
+        SubgraphMethod::RandomWalk => {
+            let config = subgraph::RandomWalkConfig {
+                walks: args.walks,
+                walk_length: args.walk_length,
+                restart_probability: args.restart_probability,
+                seed: args.seed,
+            };
+            let visited = subgraph::random_walk(&directed, &seeds, &config, args.min_visits);
+            println!("{} pages visited", visited.len());
 
-    graph
-}
+            // One `title<TAB>visits` line per page, most visited first
+            let visited: Vec<_> = visited
+                .into_iter()
+                .map(|(node, visits)| (node, visits as f64))
+                .collect();
+            score_output(&graph, &visited)
+        }
+    };
 
-#[derive(Debug)]
-struct PageIndex {
-    title: String,
-    idx: usize,
-    start_offset: u64,
-    end_offset: Option<u64>,
-}
+    write_lines(lines.iter().map(String::as_str), &args.output_file);
 
-fn read_index_file(filename: &str) -> Vec<PageIndex> {
-    const BUF_SIZE: usize = 300 * 1024 * 1024;
-    const BLOCK_SIZE: u64 = 4 * 1024 * 1024;
-    const NUM_THREADS: u64 = 16;
-
-    println!("Reading index ..");
-    let mut index_buf = String::with_capacity(BUF_SIZE);
-
-    let index_file = ParFile::new(filename.to_owned(), BLOCK_SIZE, 1, NUM_THREADS);
-    BufReader::new(index_file)
-        .read_to_string(&mut index_buf)
-        .unwrap();
-
-    println!("Processing index ..");
-    let index_list: Vec<_> = index_buf
-        .lines()
-        // .enumerate()
-        .map(|line| {
-            let (start_offset, name) = line.split_once(":").unwrap();
-            let start_offset: u64 = start_offset.parse().unwrap();
-            let name = name.trim().to_owned();
-            (name, start_offset)
-        })
-        .collect();
+    if let Some(export_file) = &args.export_file {
+        let subgraph = export::Subgraph::induced(&graph, nodes);
+        if let Err(e) = subgraph.write_file(export_file, args.export_format) {
+            eprintln!("Error: {}", e);
+            return;
+        }
+        println!(
+            "Exported {} pages and {} links",
+            subgraph.num_nodes(),
+            subgraph.num_edges()
+        );
+    }
+}
 
-    println!("Got index list");
-    let mut end_offsets: Vec<Option<u64>> = index_list
+// One `title<TAB>distance` line per page, closest first. With several seeds the distance is from
+// the closest seed, followed by the `|` separated seeds that reached the page.
+fn distance_output(
+    graph: &CsrGraph,
+    seeds: &[NodeId],
+    per_seed: &[Vec<subgraph::Reached>],
+    combine: Combine,
+) -> (Vec<String>, Vec<export::Node>) {
+    subgraph::combine(per_seed, combine)
         .iter()
-        .map(|(_, start_offset)| Some(*start_offset))
-        .collect();
-
-    println!("Got end offsets");
-    end_offsets.push(None);
-
-    let index_map: Vec<PageIndex> = index_list
-        .into_iter()
-        .zip(end_offsets[1..].into_iter())
-        .enumerate()
-        .map(|(i, ((name, start_offset), end_offset))| PageIndex {
-            title: name,
-            idx: i,
-            start_offset,
-            end_offset: *end_offset,
+        .map(|node| {
+            let mut line = format!("{}\t{}", graph.title(node.node), node.distance);
+            if seeds.len() > 1 {
+                line.push('\t');
+                line.push_str(&seed_list(graph, seeds, &node.seeds));
+            }
+            let node = export::Node {
+                node: node.node,
+                depth: Some(node.distance),
+                score: None,
+            };
+            (line, node)
         })
-        .collect();
-
-    println!("Done");
-    println!();
-
-    index_map
-}
-
-pub fn read_file_slice(file: &mut impl Read, start: u64, end: u64, buf: &mut Vec<u8>) {
-    // file.seek(SeekFrom::Start(start)).unwrap();
-    buf.resize((end.checked_sub(start).expect("start > end")) as _, 0u8);
-    file.read_exact(buf).unwrap();
+        .unzip()
 }
 
-pub fn read_page(file: &mut File, start: u64, end: Option<u64>, buf: &mut Vec<u8>) -> Page {
-    file.seek(SeekFrom::Start(start)).unwrap();
-    let end = end.unwrap_or_else(|| file.metadata().unwrap().len());
-    buf.resize((end.checked_sub(start).expect("start > end")) as _, 0u8);
-    file.read_exact(buf).unwrap();
-
-    let page: Page = serde_json::from_slice(&buf).unwrap();
-    page
+fn score_output(graph: &CsrGraph, scored: &[(NodeId, f64)]) -> (Vec<String>, Vec<export::Node>) {
+    scored
+        .iter()
+        .map(|&(node, score)| {
+            let line = format!("{}\t{}", graph.title(node), score);
+            let node = export::Node {
+                node,
+                depth: None,
+                score: Some(score),
+            };
+            (line, node)
+        })
+        .unzip()
 }
 
-pub fn write_lines<'a>(items: impl Iterator<Item = &'a str>, filename: &str) {
-    let mut out_str = String::new();
-    for item in items {
-        out_str.push_str(&item);
-        out_str.push('\n');
-    }
-
-    let output_path = Path::new(filename);
-    if let Some(parent) = output_path.parent() {
-        std::fs::create_dir_all(parent).unwrap();
-    }
-    std::fs::write(filename, out_str).unwrap();
+// Titles of the seeds, separated by `|`, which can't appear in titles
+fn seed_list(graph: &CsrGraph, seeds: &[NodeId], indices: &[usize]) -> String {
+    indices
+        .iter()
+        .map(|&i| graph.title(seeds[i]))
+        .collect::<Vec<_>>()
+        .join("|")
 }
 
-fn depth_limited_subgraph(
-    root: usize,
+// Label of the link followed at every step of `path`
+fn anchor_texts(
+    graph: &CsrGraph,
+    data_file: &str,
     index_list: &[PageIndex],
-    graph: &HashMap<usize, Vec<usize>>,
-    depth: usize,
-    list: &mut HashSet<String>,
-) {
-    if depth == 0 {
-        return;
-    }
-
-    let links = graph.get(&root).unwrap();
-    for link in links {
-        if list.insert(index_list[*link].title.clone()) {
-            depth_limited_subgraph(root, index_list, graph, depth - 1, list);
-        }
-    }
+    path: &[NodeId],
+) -> Vec<Option<String>> {
+    let mut file = File::open(data_file).unwrap();
+    let mut buf = Vec::new();
+    path.windows(2)
+        .map(|step| {
+            let page_index = &index_list[step[0] as usize];
+            let page: links::Page = read_page(
+                &mut file,
+                page_index.start_offset,
+                page_index.end_offset,
+                &mut buf,
+            );
+            paths::anchor_text(&page, graph.title(step[1])).map(str::to_owned)
+        })
+        .collect()
 }
 
-fn depth_limited_subgraph_with_fanout_factor(
-    root: usize,
-    index_list: &[PageIndex],
-    graph: &HashMap<usize, Vec<usize>>,
-    depth: f64,
-    links_factor: f64,
-    list: &mut HashSet<String>,
-) {
-    if depth <= 0.0 {
-        return;
-    }
-
-    let links = graph.get(&root).unwrap();
-    let x = links.len() as f64 / links_factor;
-    for link in links {
-        if list.insert(index_list[*link].title.clone()) {
-            depth_limited_subgraph_with_fanout_factor(
-                root,
-                index_list,
-                graph,
-                depth - x,
-                links_factor,
-                list,
-            );
+// `Title -[anchor text]-> Title -> Title`, leaving out anchor texts that aren't known
+fn format_path(graph: &CsrGraph, path: &[NodeId], anchors: &[Option<String>]) -> String {
+    let mut line = graph.title(path[0]).to_owned();
+    for (&node, anchor) in path[1..].iter().zip(anchors) {
+        match anchor {
+            Some(anchor) => line.push_str(&format!(" -[{}]-> ", anchor)),
+            None => line.push_str(" -> "),
         }
+        line.push_str(graph.title(node));
     }
+    line
 }
@@ -0,0 +1,419 @@
+//! Pipeline counters for finding out what limits a run.
+//!
+//! The XML parser, the worker pool and the writer thread all update a shared [Metrics]. At the end
+//! of a run [Metrics::summary] says where the time went, and [Metrics::prometheus_text] renders
+//! the same numbers in the Prometheus text format so they can be scraped from a file while the run
+//! is in progress.
+
+use std::{
+    fmt::Write as _,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::page_guard::LimitReason;
+
+#[cfg(test)]
+mod tests;
+
+/// Upper bounds of the extractor latency histogram buckets, in seconds
+const LATENCY_BUCKETS: [f64; 8] = [0.0001, 0.001, 0.01, 0.1, 1.0, 10.0, 60.0, f64::INFINITY];
+
+/// Number of slowest pages to remember
+const SLOWEST_PAGES: usize = 10;
+
+/// Why the XML parser didn't send a page to the extractor
+#[derive(Clone, Copy, Debug)]
+pub enum SkipReason {
+    Redirect,
+    /// Pages outside the namespaces the extractor is interested in
+    Namespace,
+    Disambiguation,
+    /// Pages that aren't disambiguation pages, when only those are extracted
+    NotDisambiguation,
+}
+
+impl SkipReason {
+    const ALL: [SkipReason; 4] = [
+        SkipReason::Redirect,
+        SkipReason::Namespace,
+        SkipReason::Disambiguation,
+        SkipReason::NotDisambiguation,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            SkipReason::Redirect => "redirect",
+            SkipReason::Namespace => "namespace",
+            SkipReason::Disambiguation => "disambiguation",
+            SkipReason::NotDisambiguation => "not_disambiguation",
+        }
+    }
+}
+
+/// Where a pipeline stage can wait on a channel
+#[derive(Clone, Copy, Debug)]
+pub enum Channel {
+    /// XML parser waiting for room in the parser queue
+    ParserSend,
+    /// Extractor workers waiting for pages in the parser queue. Workers take turns receiving, so
+    /// this is the time the pool as a whole was starved.
+    ParserRecv,
+    /// Extractor workers waiting for room in the writer queue
+    WriterSend,
+    /// Writer thread waiting for output to write
+    WriterRecv,
+}
+
+impl Channel {
+    const ALL: [Channel; 4] = [
+        Channel::ParserSend,
+        Channel::ParserRecv,
+        Channel::WriterSend,
+        Channel::WriterRecv,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Channel::ParserSend => "parser_send",
+            Channel::ParserRecv => "parser_recv",
+            Channel::WriterSend => "writer_send",
+            Channel::WriterRecv => "writer_recv",
+        }
+    }
+
+    // Number of threads that can be waiting on the channel at the same time
+    fn threads(self, workers: usize) -> usize {
+        match self {
+            Channel::WriterSend => workers,
+            Channel::ParserSend | Channel::ParserRecv | Channel::WriterRecv => 1,
+        }
+    }
+}
+
+pub struct Metrics {
+    extractor: &'static str,
+    workers: usize,
+    pages_parsed: AtomicU64,
+    pages_skipped: [AtomicU64; SkipReason::ALL.len()],
+    pages_extracted: AtomicU64,
+    pages_limited: [AtomicU64; LimitReason::ALL.len()],
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    blocked_micros: [AtomicU64; Channel::ALL.len()],
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_sum_micros: AtomicU64,
+    slowest_pages: Mutex<Vec<(Duration, String)>>,
+    // Latency of the fastest page in slowest_pages, so most pages can skip the lock
+    slowest_threshold_micros: AtomicU64,
+}
+
+impl Metrics {
+    /// `extractor` is used as a label on the latency histogram. Blocked times are compared per
+    /// thread, assuming a worker for every thread of the rayon pool unless told otherwise with
+    /// [with_workers](Self::with_workers).
+    pub fn new(extractor: &'static str) -> Self {
+        Self {
+            extractor,
+            workers: rayon::current_num_threads(),
+            pages_parsed: AtomicU64::new(0),
+            pages_skipped: Default::default(),
+            pages_extracted: AtomicU64::new(0),
+            pages_limited: Default::default(),
+            bytes_in: AtomicU64::new(0),
+            bytes_out: AtomicU64::new(0),
+            blocked_micros: Default::default(),
+            latency_buckets: Default::default(),
+            latency_sum_micros: AtomicU64::new(0),
+            slowest_pages: Mutex::new(Vec::new()),
+            slowest_threshold_micros: AtomicU64::new(0),
+        }
+    }
+
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    /// A page was read from the XML file
+    pub fn page_parsed(&self) {
+        self.pages_parsed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn page_skipped(&self, reason: SkipReason) {
+        self.pages_skipped[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A page exceeded the limits of the [PageGuard](crate::page_guard::PageGuard)
+    pub fn page_limited(&self, reason: LimitReason) {
+        self.pages_limited[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The wikitext of a page was handed to the extractor
+    pub fn bytes_in(&self, n: usize) {
+        self.bytes_in.fetch_add(n as _, Ordering::Relaxed);
+    }
+
+    /// Extractor output was written to the data file
+    pub fn bytes_out(&self, n: usize) {
+        self.bytes_out.fetch_add(n as _, Ordering::Relaxed);
+    }
+
+    pub fn blocked(&self, channel: Channel, duration: Duration) {
+        self.blocked_micros[channel as usize]
+            .fetch_add(duration.as_micros() as _, Ordering::Relaxed);
+    }
+
+    /// The extractor took `duration` to process the page called `title`
+    pub fn extracted(&self, title: &str, duration: Duration) {
+        self.pages_extracted.fetch_add(1, Ordering::Relaxed);
+
+        let secs = duration.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|&le| secs <= le)
+            .unwrap_or(LATENCY_BUCKETS.len() - 1);
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+
+        let micros = duration.as_micros() as u64;
+        self.latency_sum_micros.fetch_add(micros, Ordering::Relaxed);
+
+        if micros <= self.slowest_threshold_micros.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut slowest = self.slowest_pages.lock().unwrap();
+        slowest.push((duration, title.to_owned()));
+        slowest.sort_by_key(|(duration, _)| std::cmp::Reverse(*duration));
+        slowest.truncate(SLOWEST_PAGES);
+        if slowest.len() == SLOWEST_PAGES {
+            let threshold = slowest.last().unwrap().0.as_micros() as u64;
+            self.slowest_threshold_micros
+                .store(threshold, Ordering::Relaxed);
+        }
+    }
+
+    /// The slowest pages seen so far, slowest first
+    pub fn slowest_pages(&self) -> Vec<(Duration, String)> {
+        self.slowest_pages.lock().unwrap().clone()
+    }
+
+    /// Human readable report, meant to be printed at the end of a run
+    pub fn summary(&self) -> String {
+        let mut out = String::new();
+        let load = |x: &AtomicU64| x.load(Ordering::Relaxed);
+
+        let _ = writeln!(out, "Pages parsed: {}", load(&self.pages_parsed));
+        for reason in SkipReason::ALL {
+            let _ = writeln!(
+                out,
+                "Pages skipped ({}): {}",
+                reason.name(),
+                load(&self.pages_skipped[reason as usize])
+            );
+        }
+
+        let extracted = load(&self.pages_extracted);
+        let total_secs = load(&self.latency_sum_micros) as f64 / 1e6;
+        let _ = writeln!(out, "Pages extracted: {}", extracted);
+        for reason in LimitReason::ALL {
+            let _ = writeln!(
+                out,
+                "Pages over limit ({}): {}",
+                reason.name(),
+                load(&self.pages_limited[reason as usize])
+            );
+        }
+        let _ = writeln!(
+            out,
+            "Bytes in: {:.2} MB, bytes out: {:.2} MB",
+            load(&self.bytes_in) as f64 / (1024.0 * 1024.0),
+            load(&self.bytes_out) as f64 / (1024.0 * 1024.0),
+        );
+        let _ = writeln!(
+            out,
+            "Extractor ({}): {:.2}s CPU total, {:.3}ms per page",
+            self.extractor,
+            total_secs,
+            1000.0 * total_secs / extracted.max(1) as f64,
+        );
+
+        for channel in Channel::ALL {
+            let threads = channel.threads(self.workers);
+            let _ = write!(
+                out,
+                "Blocked on {}: {:.2}s",
+                channel.name(),
+                load(&self.blocked_micros[channel as usize]) as f64 / 1e6
+            );
+            if threads > 1 {
+                let _ = write!(
+                    out,
+                    " ({:.2}s per thread)",
+                    self.blocked_per_thread(channel) / 1e6
+                );
+            }
+            let _ = writeln!(out);
+        }
+        let _ = writeln!(out, "Bottleneck: {}", self.bottleneck());
+
+        let _ = writeln!(out, "Slowest pages:");
+        for (duration, title) in self.slowest_pages() {
+            let _ = writeln!(out, "  {:.3}s {}", duration.as_secs_f64(), title);
+        }
+
+        out
+    }
+
+    // Microseconds a single thread waiting on `channel` spent blocked, on average
+    fn blocked_per_thread(&self, channel: Channel) -> f64 {
+        self.blocked_micros[channel as usize].load(Ordering::Relaxed) as f64
+            / channel.threads(self.workers) as f64
+    }
+
+    // Guess the limiting stage from where the other stages spend their time waiting. Both sides of
+    // a queue are compared per thread, so the number of workers doesn't tip the balance.
+    fn bottleneck(&self) -> &'static str {
+        let workers_sending = self.blocked_per_thread(Channel::WriterSend);
+        let writer_receiving = self.blocked_per_thread(Channel::WriterRecv);
+        let xml_sending = self.blocked_per_thread(Channel::ParserSend);
+        let workers_receiving = self.blocked_per_thread(Channel::ParserRecv);
+
+        if workers_sending > writer_receiving {
+            // Workers wait for the writer more than it waits for them
+            "writer"
+        } else if xml_sending > workers_receiving {
+            // The XML parser produces pages faster than the workers consume them
+            "extractor"
+        } else {
+            // The workers are waiting for pages
+            "input (disk or XML parsing)"
+        }
+    }
+
+    /// Render the metrics in the Prometheus text exposition format
+    pub fn prometheus_text(&self) -> String {
+        let mut out = String::new();
+        let load = |x: &AtomicU64| x.load(Ordering::Relaxed);
+
+        let mut counter = |name: &str, help: &str, values: &[(String, u64)]| {
+            let _ = writeln!(out, "# HELP wikipedia_parser_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE wikipedia_parser_{} counter", name);
+            for (labels, value) in values {
+                let _ = writeln!(out, "wikipedia_parser_{}{} {}", name, labels, value);
+            }
+        };
+
+        counter(
+            "pages_parsed_total",
+            "Pages read from the XML file",
+            &[(String::new(), load(&self.pages_parsed))],
+        );
+        counter(
+            "pages_skipped_total",
+            "Pages not sent to the extractor",
+            &SkipReason::ALL.map(|r| {
+                (
+                    format!("{{reason=\"{}\"}}", r.name()),
+                    load(&self.pages_skipped[r as usize]),
+                )
+            }),
+        );
+        counter(
+            "pages_limited_total",
+            "Pages that exceeded the per-page size or time limit",
+            &LimitReason::ALL.map(|r| {
+                (
+                    format!("{{reason=\"{}\"}}", r.name()),
+                    load(&self.pages_limited[r as usize]),
+                )
+            }),
+        );
+        counter(
+            "bytes_in_total",
+            "Wikitext bytes sent to the extractor",
+            &[(String::new(), load(&self.bytes_in))],
+        );
+        counter(
+            "bytes_out_total",
+            "Bytes written to the data file",
+            &[(String::new(), load(&self.bytes_out))],
+        );
+        counter(
+            "blocked_microseconds_total",
+            "Time spent waiting on a channel",
+            &Channel::ALL.map(|c| {
+                (
+                    format!("{{channel=\"{}\"}}", c.name()),
+                    load(&self.blocked_micros[c as usize]),
+                )
+            }),
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP wikipedia_parser_extractor_workers Threads running the extractor"
+        );
+        let _ = writeln!(out, "# TYPE wikipedia_parser_extractor_workers gauge");
+        let _ = writeln!(out, "wikipedia_parser_extractor_workers {}", self.workers);
+
+        let name = "wikipedia_parser_extractor_latency_seconds";
+        let _ = writeln!(out, "# HELP {} Time spent extracting a page", name);
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (le, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += load(count);
+            let le = if le.is_infinite() {
+                "+Inf".to_string()
+            } else {
+                le.to_string()
+            };
+            let _ = writeln!(
+                out,
+                "{}_bucket{{extractor=\"{}\",le=\"{}\"}} {}",
+                name, self.extractor, le, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "{}_sum{{extractor=\"{}\"}} {}",
+            name,
+            self.extractor,
+            load(&self.latency_sum_micros) as f64 / 1e6
+        );
+        let _ = writeln!(
+            out,
+            "{}_count{{extractor=\"{}\"}} {}",
+            name, self.extractor, cumulative
+        );
+
+        out
+    }
+
+    /// Write [prometheus_text](Self::prometheus_text) to `filename`.
+    /// The file is replaced atomically so scrapers never see a partial file.
+    pub fn write_prometheus_file(&self, filename: &str) -> std::io::Result<()> {
+        let tmp = format!("{}.tmp", filename);
+        std::fs::write(&tmp, self.prometheus_text())?;
+        std::fs::rename(tmp, filename)
+    }
+}
+
+/// Rewrite the metrics file every `interval` until the program exits
+pub fn spawn_file_exporter(metrics: Arc<Metrics>, filename: String, interval: Duration) {
+    if let Some(parent) = Path::new(&filename).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+
+    thread::spawn(move || loop {
+        if let Err(e) = metrics.write_prometheus_file(&filename) {
+            eprintln!("Failed to write metrics to {}: {}", filename, e);
+        }
+        thread::sleep(interval);
+    });
+}
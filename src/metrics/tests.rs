@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use super::{Channel, Metrics, SkipReason};
+use crate::page_guard::LimitReason;
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+#[test]
+fn counters() {
+    let metrics = Metrics::new("links").with_workers(4);
+    for _ in 0..5 {
+        metrics.page_parsed();
+    }
+    metrics.page_skipped(SkipReason::Redirect);
+    metrics.page_skipped(SkipReason::Redirect);
+    metrics.page_limited(LimitReason::Timeout);
+    metrics.bytes_in(2 * 1024 * 1024);
+    metrics.bytes_out(1024 * 1024);
+    metrics.blocked(Channel::WriterSend, secs(8));
+    metrics.extracted("Fast", Duration::from_millis(1));
+    metrics.extracted("Slow", secs(2));
+
+    let summary = metrics.summary();
+    for line in [
+        "Pages parsed: 5",
+        "Pages skipped (redirect): 2",
+        "Pages skipped (namespace): 0",
+        "Pages extracted: 2",
+        "Pages over limit (timeout): 1",
+        "Bytes in: 2.00 MB, bytes out: 1.00 MB",
+        "Blocked on writer_send: 8.00s (2.00s per thread)",
+        "Blocked on parser_recv: 0.00s",
+        "  2.000s Slow",
+        "  0.001s Fast",
+    ] {
+        assert!(
+            summary.lines().any(|l| l == line),
+            "{} in {}",
+            line,
+            summary
+        );
+    }
+}
+
+#[test]
+fn slowest_pages() {
+    let metrics = Metrics::new("links");
+    for millis in 0..30 {
+        metrics.extracted(&millis.to_string(), Duration::from_millis(millis));
+    }
+
+    let slowest = metrics.slowest_pages();
+    assert_eq!(slowest.len(), 10);
+    assert_eq!(slowest[0], (Duration::from_millis(29), "29".to_string()));
+    assert_eq!(slowest[9], (Duration::from_millis(20), "20".to_string()));
+}
+
+#[test]
+fn prometheus_text() {
+    let metrics = Metrics::new("links").with_workers(2);
+    metrics.page_parsed();
+    metrics.page_skipped(SkipReason::Namespace);
+    metrics.blocked(Channel::ParserRecv, Duration::from_micros(1500));
+    metrics.extracted("A", Duration::from_micros(50));
+    metrics.extracted("B", Duration::from_millis(5));
+
+    let text = metrics.prometheus_text();
+    for line in [
+        "# TYPE wikipedia_parser_pages_parsed_total counter",
+        "wikipedia_parser_pages_parsed_total 1",
+        "wikipedia_parser_pages_skipped_total{reason=\"namespace\"} 1",
+        "wikipedia_parser_pages_limited_total{reason=\"timeout\"} 0",
+        "wikipedia_parser_blocked_microseconds_total{channel=\"parser_recv\"} 1500",
+        "wikipedia_parser_extractor_workers 2",
+        "# TYPE wikipedia_parser_extractor_latency_seconds histogram",
+        "wikipedia_parser_extractor_latency_seconds_bucket{extractor=\"links\",le=\"0.0001\"} 1",
+        "wikipedia_parser_extractor_latency_seconds_bucket{extractor=\"links\",le=\"0.001\"} 1",
+        "wikipedia_parser_extractor_latency_seconds_bucket{extractor=\"links\",le=\"0.01\"} 2",
+        "wikipedia_parser_extractor_latency_seconds_bucket{extractor=\"links\",le=\"+Inf\"} 2",
+        "wikipedia_parser_extractor_latency_seconds_sum{extractor=\"links\"} 0.00505",
+        "wikipedia_parser_extractor_latency_seconds_count{extractor=\"links\"} 2",
+    ] {
+        assert!(text.lines().any(|l| l == line), "{} in {}", line, text);
+    }
+    // Every sample line is a name, optional labels and a number
+    for line in text.lines().filter(|l| !l.starts_with('#')) {
+        let (_, value) = line.rsplit_once(' ').unwrap();
+        assert!(value.parse::<f64>().is_ok(), "{}", line);
+    }
+}
+
+#[test]
+fn bottleneck() {
+    // Every worker blocked a little while sending adds up to more than the writer's wait, but per
+    // thread they wait less than the writer does
+    let metrics = Metrics::new("links").with_workers(16);
+    metrics.blocked(Channel::WriterSend, secs(32));
+    metrics.blocked(Channel::WriterRecv, secs(10));
+    metrics.blocked(Channel::ParserSend, secs(9));
+    metrics.blocked(Channel::ParserRecv, secs(1));
+    assert_eq!(metrics.bottleneck(), "extractor");
+
+    let metrics = Metrics::new("links").with_workers(16);
+    metrics.blocked(Channel::WriterSend, secs(16 * 9));
+    metrics.blocked(Channel::WriterRecv, secs(1));
+    metrics.blocked(Channel::ParserSend, secs(9));
+    assert_eq!(metrics.bottleneck(), "writer");
+
+    // Workers starved of pages
+    let metrics = Metrics::new("links").with_workers(16);
+    metrics.blocked(Channel::WriterSend, secs(16));
+    metrics.blocked(Channel::WriterRecv, secs(9));
+    metrics.blocked(Channel::ParserSend, secs(1));
+    metrics.blocked(Channel::ParserRecv, secs(9));
+    assert_eq!(metrics.bottleneck(), "input (disk or XML parsing)");
+}
@@ -0,0 +1,207 @@
+//! Time and size limits for extracting a single page.
+//!
+//! Some pages (huge lists, malformed tables) make the wikitext parser backtrack for minutes. A
+//! [PageGuard] skips pages above a size limit and stops extraction once a time limit is reached,
+//! then either drops the page or runs a cheap fallback extractor on it. Every page that hits a
+//! limit is recorded in an optional JSON lines report.
+//!
+//! Rust threads can't be interrupted, so the time limit is cooperative: long running extractors
+//! call [check_deadline] regularly, which unwinds back to the guard once the deadline has passed.
+
+use std::{
+    cell::Cell,
+    fs::File,
+    io::{BufWriter, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::metrics::Metrics;
+
+#[cfg(test)]
+mod tests;
+
+/// Only look at the clock once every this many calls to [check_deadline]
+const CHECK_INTERVAL: u32 = 1024;
+
+thread_local! {
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+    static CHECK_COUNTER: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Payload of the unwind started by [check_deadline]
+struct DeadlineExceeded;
+
+/// Abort the current extraction if the deadline set by the [PageGuard] has passed.
+/// Cheap enough to be called once per parsed character.
+pub fn check_deadline() {
+    let count = CHECK_COUNTER.with(|c| {
+        let count = c.get().wrapping_add(1);
+        c.set(count);
+        count
+    });
+    if !count.is_multiple_of(CHECK_INTERVAL) {
+        return;
+    }
+
+    if let Some(deadline) = DEADLINE.with(Cell::get) {
+        if Instant::now() > deadline {
+            // resume_unwind doesn't run the panic hook, so this doesn't print anything
+            panic::resume_unwind(Box::new(DeadlineExceeded));
+        }
+    }
+}
+
+// Run f with a deadline. Returns None if f didn't finish in time.
+fn with_deadline<T>(timeout: Duration, f: impl FnOnce() -> T) -> Option<T> {
+    DEADLINE.with(|d| d.set(Some(Instant::now() + timeout)));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    DEADLINE.with(|d| d.set(None));
+
+    match result {
+        Ok(x) => Some(x),
+        Err(payload) if payload.is::<DeadlineExceeded>() => None,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+/// What to do with a page that exceeds a limit
+#[derive(ValueEnum, Clone, Copy, Debug, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    /// Leave the page out of the output
+    Skip,
+    /// Run the cheap fallback extractor instead
+    #[default]
+    Fallback,
+}
+
+/// Which limit a page exceeded
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitReason {
+    TooLarge,
+    Timeout,
+}
+
+impl LimitReason {
+    pub const ALL: [LimitReason; 2] = [LimitReason::TooLarge, LimitReason::Timeout];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LimitReason::TooLarge => "too_large",
+            LimitReason::Timeout => "timeout",
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReportEntry<'a> {
+    title: &'a str,
+    bytes: usize,
+    reason: LimitReason,
+    action: LimitAction,
+    elapsed_secs: f64,
+}
+
+pub struct PageGuard {
+    max_bytes: Option<usize>,
+    timeout: Option<Duration>,
+    action: LimitAction,
+    report: Option<Mutex<BufWriter<File>>>,
+}
+
+impl PageGuard {
+    /// A guard that never intervenes
+    pub fn unlimited() -> Self {
+        Self {
+            max_bytes: None,
+            timeout: None,
+            action: LimitAction::Skip,
+            report: None,
+        }
+    }
+
+    pub fn new(
+        max_bytes: Option<usize>,
+        timeout: Option<Duration>,
+        action: LimitAction,
+        report_file: Option<&str>,
+    ) -> std::io::Result<Self> {
+        let report = match report_file {
+            Some(filename) => Some(Mutex::new(BufWriter::new(File::create(filename)?))),
+            None => None,
+        };
+
+        Ok(Self {
+            max_bytes,
+            timeout,
+            action,
+            report,
+        })
+    }
+
+    /// Run `extractor` on the page within the limits. Returns None if the page was skipped.
+    pub fn run<F, G>(
+        &self,
+        input: &[u8],
+        title: &str,
+        extractor: &F,
+        fallback: &G,
+        metrics: &Metrics,
+    ) -> Option<String>
+    where
+        F: Fn(&[u8], &str) -> String,
+        G: Fn(&[u8], &str) -> String,
+    {
+        let start = Instant::now();
+
+        let too_large = self.max_bytes.is_some_and(|max| input.len() > max);
+        let reason = if too_large {
+            LimitReason::TooLarge
+        } else {
+            let output = match self.timeout {
+                Some(timeout) => with_deadline(timeout, || extractor(input, title)),
+                None => Some(extractor(input, title)),
+            };
+            match output {
+                Some(output) => return Some(output),
+                None => LimitReason::Timeout,
+            }
+        };
+
+        metrics.page_limited(reason);
+        self.record(ReportEntry {
+            title,
+            bytes: input.len(),
+            reason,
+            action: self.action,
+            elapsed_secs: start.elapsed().as_secs_f64(),
+        });
+
+        match self.action {
+            LimitAction::Skip => None,
+            LimitAction::Fallback => Some(fallback(input, title)),
+        }
+    }
+
+    fn record(&self, entry: ReportEntry) {
+        if let Some(report) = &self.report {
+            let mut report = report.lock().unwrap();
+            let _ = serde_json::to_writer(&mut *report, &entry);
+            let _ = report.write_all(b"\n");
+        }
+    }
+
+    /// Flush the report file
+    pub fn flush(&self) -> std::io::Result<()> {
+        match &self.report {
+            Some(report) => report.lock().unwrap().flush(),
+            None => Ok(()),
+        }
+    }
+}
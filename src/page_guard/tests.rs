@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use super::{check_deadline, LimitAction, PageGuard};
+use crate::extractors::wikitext::extract_fallback;
+use crate::metrics::Metrics;
+
+fn slow_extractor(_: &[u8], _: &str) -> String {
+    loop {
+        check_deadline();
+    }
+}
+
+fn fast_extractor(input: &[u8], _: &str) -> String {
+    String::from_utf8_lossy(input).to_string()
+}
+
+fn fallback(_: &[u8], _: &str) -> String {
+    "fallback".to_string()
+}
+
+#[test]
+fn unlimited() {
+    let guard = PageGuard::unlimited();
+    let metrics = Metrics::new("test");
+
+    let output = guard.run(b"text", "Title", &fast_extractor, &fallback, &metrics);
+    assert_eq!(output.as_deref(), Some("text"));
+}
+
+#[test]
+fn timeout_runs_fallback() {
+    let guard = PageGuard::new(
+        None,
+        Some(Duration::from_millis(50)),
+        LimitAction::Fallback,
+        None,
+    )
+    .unwrap();
+    let metrics = Metrics::new("test");
+
+    let output = guard.run(b"text", "Title", &slow_extractor, &fallback, &metrics);
+    assert_eq!(output.as_deref(), Some("fallback"));
+
+    // The deadline must not leak into the next page
+    let output = guard.run(b"text", "Title", &fast_extractor, &fallback, &metrics);
+    assert_eq!(output.as_deref(), Some("text"));
+}
+
+#[test]
+fn too_large_is_skipped() {
+    let guard = PageGuard::new(Some(3), None, LimitAction::Skip, None).unwrap();
+    let metrics = Metrics::new("test");
+
+    let output = guard.run(b"text", "Title", &fast_extractor, &fallback, &metrics);
+    assert_eq!(output, None);
+
+    let output = guard.run(b"txt", "Title", &fast_extractor, &fallback, &metrics);
+    assert_eq!(output.as_deref(), Some("txt"));
+}
+
+#[test]
+#[should_panic(expected = "not a timeout")]
+fn other_panics_propagate() {
+    let guard =
+        PageGuard::new(None, Some(Duration::from_secs(60)), LimitAction::Skip, None).unwrap();
+    let metrics = Metrics::new("test");
+
+    guard.run(
+        b"text",
+        "Title",
+        &|_: &[u8], _: &str| -> String { panic!("not a timeout") },
+        &fallback,
+        &metrics,
+    );
+}
+
+#[test]
+fn fallback_extractor_strips_markup() {
+    let input = b"'''Foo''' is a [[bar|baz]]{{citation needed}}<ref name=x>ref</ref>.\n\
+        [[File:x.jpg|thumb|caption]]<!-- comment -->\n\
+        == Section ==\n\
+        {| class=wikitable\n|-\n| cell\n|}\n\
+        Text <small>small</small>.";
+    let output = extract_fallback(input, "Foo");
+    let tree: serde_json::Value = serde_json::from_str(&output).unwrap();
+
+    assert_eq!(tree["text"], "Foo is a baz.\n");
+    assert_eq!(tree["children"][0]["section_name"], "Section");
+    assert_eq!(tree["children"][0]["text"], "Text small.\n");
+}
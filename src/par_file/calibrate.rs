@@ -0,0 +1,229 @@
+//! Pick a [ParFileConfig](super::ParFileConfig) for the storage the input file lives on.
+//!
+//! The best block size and thread count differ a lot between SATA SSDs, NVMe drives and network
+//! filesystems, so instead of guessing we read a few slices of the file with different settings
+//! and keep the fastest one. The result is saved next to the file, so later runs on the same file
+//! don't pay for it again.
+
+use std::{
+    fs::File,
+    io::Read,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ParFile, ParFileConfig};
+
+const M: u64 = 1024 * 1024;
+
+/// Files smaller than this are read with the default config. Measuring them would take longer
+/// than reading them.
+const MIN_CALIBRATION_FILE_SIZE: u64 = 256 * M;
+
+/// Upper bound on the memory a calibrated config may use for its buffers
+const MAX_BUFFERED_BYTES: u64 = 2 * 1024 * M;
+
+/// Stop adding threads once throughput improves by less than this factor
+const MIN_IMPROVEMENT: f64 = 1.1;
+
+const THREAD_COUNTS: &[u64] = &[1, 2, 4, 8, 16, 32, 64];
+const BLOCK_SIZES: &[u64] = &[M, 4 * M, 16 * M, 64 * M, 128 * M];
+
+/// Outcome of [calibrate](self::calibrate)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub config: ParFileConfig,
+    /// Measured throughput of `config`. `None` if the file was too small to measure.
+    pub bytes_per_sec: Option<f64>,
+}
+
+/// Measure read throughput of `filename` with a number of candidate configs and return the
+/// fastest one.
+///
+/// First the thread count is doubled until it stops paying off, then block sizes are tried with
+/// that thread count. Each trial reads a different slice of the file so that the page cache
+/// doesn't flatter later trials. The whole process takes roughly `budget`.
+pub fn calibrate(filename: &str, budget: Duration) -> std::io::Result<Calibration> {
+    let file_size = File::open(filename)?.metadata()?.len();
+    let default = ParFileConfig::default();
+
+    if file_size < MIN_CALIBRATION_FILE_SIZE {
+        return Ok(Calibration {
+            config: default,
+            bytes_per_sec: None,
+        });
+    }
+
+    let max_trials = (THREAD_COUNTS.len() + BLOCK_SIZES.len()) as u64;
+    let trial_duration = budget / max_trials as u32;
+    let slice_size = file_size / max_trials;
+    let mut trial_idx = 0;
+
+    let mut run_trial = |config: ParFileConfig| {
+        let start_offset = trial_idx * slice_size;
+        trial_idx += 1;
+        measure(filename, config, start_offset, slice_size, trial_duration)
+    };
+
+    // Find the thread count
+    let mut best = default;
+    let mut best_rate = 0.0;
+    for &num_threads in THREAD_COUNTS {
+        let config = ParFileConfig {
+            num_threads,
+            ..default
+        };
+        if config.buffered_bytes() > MAX_BUFFERED_BYTES {
+            break;
+        }
+
+        let rate = run_trial(config)?;
+        let improved = rate > best_rate * MIN_IMPROVEMENT;
+        if rate > best_rate {
+            best = config;
+            best_rate = rate;
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    // Find the block size for that thread count
+    for &block_size in BLOCK_SIZES {
+        let config = ParFileConfig { block_size, ..best };
+        if block_size == best.block_size || config.buffered_bytes() > MAX_BUFFERED_BYTES {
+            continue;
+        }
+
+        let rate = run_trial(config)?;
+        if rate > best_rate {
+            best = config;
+            best_rate = rate;
+        }
+    }
+
+    Ok(Calibration {
+        config: best,
+        bytes_per_sec: Some(best_rate),
+    })
+}
+
+// Read up to `max_bytes` starting at `start_offset`, for at most `duration`, and return the rate
+// in bytes per second.
+fn measure(
+    filename: &str,
+    config: ParFileConfig,
+    start_offset: u64,
+    max_bytes: u64,
+    duration: Duration,
+) -> std::io::Result<f64> {
+    let mut file = ParFile::with_config_at(filename.to_owned(), config, start_offset);
+    let mut buf = vec![0u8; config.block_size as _];
+
+    let start = Instant::now();
+    let mut total = 0;
+    while total < max_bytes && start.elapsed() < duration {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        total += n as u64;
+    }
+
+    Ok(total as f64 / start.elapsed().as_secs_f64())
+}
+
+// A calibration saved next to the file it was measured on, valid while the file is unchanged
+#[derive(Serialize, Deserialize)]
+struct SavedCalibration {
+    file_size: u64,
+    modified_secs: u64,
+    calibration: Calibration,
+}
+
+pub(super) fn saved_calibration_path(filename: &str) -> String {
+    format!("{}.calibration.json", filename)
+}
+
+// Size and modification time of the file, which tell whether a saved calibration still applies
+fn file_version(filename: &str) -> std::io::Result<(u64, u64)> {
+    let metadata = std::fs::metadata(filename)?;
+    let modified_secs = metadata
+        .modified()?
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |modified| modified.as_secs());
+    Ok((metadata.len(), modified_secs))
+}
+
+/// The calibration saved by [save_calibration] for `filename`, unless the file has changed since
+pub(super) fn load_calibration(filename: &str) -> Option<Calibration> {
+    let saved = std::fs::read(saved_calibration_path(filename)).ok()?;
+    let saved: SavedCalibration = serde_json::from_slice(&saved).ok()?;
+    let (file_size, modified_secs) = file_version(filename).ok()?;
+    (saved.file_size == file_size && saved.modified_secs == modified_secs)
+        .then_some(saved.calibration)
+}
+
+pub(super) fn save_calibration(filename: &str, calibration: &Calibration) -> std::io::Result<()> {
+    let (file_size, modified_secs) = file_version(filename)?;
+    let saved = SavedCalibration {
+        file_size,
+        modified_secs,
+        calibration: *calibration,
+    };
+    std::fs::write(
+        saved_calibration_path(filename),
+        serde_json::to_vec(&saved).map_err(std::io::Error::other)?,
+    )
+}
+
+/// Build a config from user supplied settings, calibrating whatever wasn't supplied. The
+/// calibration is reused from an earlier run on the same file when there is one.
+pub fn auto_config(
+    filename: &str,
+    num_threads: Option<u64>,
+    block_size: Option<u64>,
+    budget: Duration,
+) -> std::io::Result<ParFileConfig> {
+    let mut config = match (num_threads, block_size) {
+        (Some(_), Some(_)) => ParFileConfig::default(),
+        _ => {
+            let calibration = match load_calibration(filename) {
+                Some(calibration) => {
+                    println!("Using saved calibration for {}", filename);
+                    calibration
+                }
+                None => {
+                    println!("Calibrating reader for {} ..", filename);
+                    let calibration = calibrate(filename, budget)?;
+                    if calibration.bytes_per_sec.is_some() {
+                        if let Err(e) = save_calibration(filename, &calibration) {
+                            println!("Could not save the calibration: {}", e);
+                        }
+                    }
+                    calibration
+                }
+            };
+            match calibration.bytes_per_sec {
+                Some(rate) => println!(
+                    "Calibrated: {} threads, {} MB blocks ({:.2} MB/s)",
+                    calibration.config.num_threads,
+                    calibration.config.block_size / M,
+                    rate / M as f64,
+                ),
+                None => println!("File too small to calibrate, using defaults"),
+            }
+            calibration.config
+        }
+    };
+
+    if let Some(num_threads) = num_threads {
+        config.num_threads = num_threads;
+    }
+    if let Some(block_size) = block_size {
+        config.block_size = block_size;
+    }
+
+    Ok(config)
+}
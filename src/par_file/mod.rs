@@ -12,11 +12,15 @@ use std::{
     thread,
 };
 
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+mod calibrate;
 #[cfg(test)]
 mod tests;
 
+pub use calibrate::{auto_config, calibrate, Calibration};
+
 /// Parallel File Reader to saturate NVMe read queues
 /// Spawns N threads which read 0..buf_size, buf_size..2*buf_size, .., slices of the file in
 /// parallel using multiple syscalls, and send these through a Channel to the main thread.
@@ -46,20 +50,62 @@ pub enum ParFileError {
     SendError(#[from] SendError<Vec<u8>>),
 }
 
+/// Tuning parameters for a [ParFile](self::ParFile)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParFileConfig {
+    /// Size of each buffer read by a single syscall
+    pub block_size: u64,
+    /// Number of buffers each thread can fill ahead of the reader
+    pub queue_size: u64,
+    /// Number of reader threads
+    pub num_threads: u64,
+}
+
+impl Default for ParFileConfig {
+    fn default() -> Self {
+        Self {
+            block_size: 16 * 1024 * 1024,
+            queue_size: 1,
+            num_threads: 16,
+        }
+    }
+}
+
+impl ParFileConfig {
+    /// Memory held by the buffers of a ParFile using this config
+    pub fn buffered_bytes(&self) -> u64 {
+        self.block_size * self.queue_size * self.num_threads
+    }
+}
+
 impl ParFile {
     pub fn new(filename: String, block_size: u64, queue_size: u64, num_threads: u64) -> Self {
+        let config = ParFileConfig {
+            block_size,
+            queue_size,
+            num_threads,
+        };
+        Self::with_config(filename, config)
+    }
+
+    pub fn with_config(filename: String, config: ParFileConfig) -> Self {
+        Self::with_config_at(filename, config, 0)
+    }
+
+    /// Same as [with_config](Self::with_config), but start reading from `start_offset` instead of
+    /// the beginning of the file.
+    pub fn with_config_at(filename: String, config: ParFileConfig, start_offset: u64) -> Self {
         let active_thread_count = Arc::new(AtomicU64::new(0));
 
         let mut threads = Vec::new();
 
-        for thread_idx in 0..num_threads {
+        for thread_idx in 0..config.num_threads {
             let thread = ThreadHandle::new_spawn(
                 filename.clone(),
-                queue_size,
-                block_size,
+                config,
+                start_offset,
                 active_thread_count.clone(),
                 thread_idx,
-                num_threads,
             );
             threads.push(thread);
         }
@@ -136,12 +182,17 @@ struct ThreadHandle {
 impl ThreadHandle {
     fn new_spawn(
         filename: String,
-        queue_size: u64,
-        block_size: u64,
+        config: ParFileConfig,
+        start_offset: u64,
         active_thread_count: Arc<AtomicU64>,
         thread_idx: u64,
-        num_threads: u64,
     ) -> Self {
+        let ParFileConfig {
+            block_size,
+            queue_size,
+            num_threads,
+        } = config;
+
         // Channel for buffers filled with data read from file
         let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(queue_size as _);
         // Return channel for buffers completely read by the user
@@ -158,7 +209,9 @@ impl ThreadHandle {
             active_thread_count.fetch_add(1, Ordering::Relaxed);
 
             let mut file = File::open(filename)?;
-            file.seek(std::io::SeekFrom::Start(thread_idx * block_size))?;
+            file.seek(std::io::SeekFrom::Start(
+                start_offset + thread_idx * block_size,
+            ))?;
 
             let reader = Reader {
                 file,
@@ -1,6 +1,7 @@
 use std::{io::Read, sync::atomic::Ordering, thread, time::Duration};
 
-use super::ParFile;
+use super::calibrate::{load_calibration, save_calibration, saved_calibration_path};
+use super::{calibrate, Calibration, ParFile, ParFileConfig};
 
 fn make_tempfile(name: &str) -> String {
     let tempdir = std::env::temp_dir();
@@ -129,3 +130,61 @@ fn drop_test() {
     thread::sleep(Duration::from_secs_f64(0.1));
     assert_eq!(active_threads.load(Ordering::Relaxed), 0);
 }
+
+#[test]
+fn read_from_offset() {
+    let src = "Lorem ipsum dolor sit amet.";
+
+    let filename = make_tempfile("read_from_offset");
+    std::fs::write(&filename, src).unwrap();
+
+    let config = ParFileConfig {
+        block_size: 2,
+        queue_size: 2,
+        num_threads: 3,
+    };
+    let mut parfile = ParFile::with_config_at(filename.clone(), config, 6);
+    let mut out = String::new();
+    parfile.read_to_string(&mut out).unwrap();
+
+    assert_eq!(out, &src[6..]);
+
+    let mut parfile = ParFile::with_config_at(filename, config, 100);
+    let mut out = String::new();
+    parfile.read_to_string(&mut out).unwrap();
+
+    assert_eq!(out, "");
+}
+
+#[test]
+fn calibrate_small_file() {
+    let filename = make_tempfile("calibrate_small_file");
+    std::fs::write(&filename, "12345").unwrap();
+
+    let calibration = calibrate(&filename, Duration::from_secs(1)).unwrap();
+    assert_eq!(calibration.config, ParFileConfig::default());
+    assert!(calibration.bytes_per_sec.is_none());
+}
+
+#[test]
+fn saved_calibration() {
+    let filename = make_tempfile("saved_calibration");
+    std::fs::write(&filename, "12345").unwrap();
+    let _ = std::fs::remove_file(saved_calibration_path(&filename));
+    assert_eq!(load_calibration(&filename), None);
+
+    let calibration = Calibration {
+        config: ParFileConfig {
+            block_size: 4 * 1024 * 1024,
+            queue_size: 1,
+            num_threads: 8,
+        },
+        bytes_per_sec: Some(1e9),
+    };
+    save_calibration(&filename, &calibration).unwrap();
+    assert_eq!(load_calibration(&filename), Some(calibration));
+
+    // A different file under the same name needs a new calibration
+    std::fs::write(&filename, "123456").unwrap();
+    assert_eq!(load_calibration(&filename), None);
+}
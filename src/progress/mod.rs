@@ -0,0 +1,333 @@
+//! Progress tracking and reporting.
+//!
+//! [Progress](self::Progress) turns raw counters into [ProgressEvent](self::ProgressEvent)s, and a
+//! [ProgressReporter](self::ProgressReporter) decides how to show them: a bar redrawn in place for
+//! terminals, periodic log lines for CI and batch schedulers, or newline-delimited JSON for
+//! machines. JSON events go to stderr or a file, never to stdout, so they don't mix with the
+//! status messages printed there.
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[cfg(test)]
+mod tests;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+#[derive(Clone)]
+pub struct Progress {
+    pub total: u64,
+    pub start: SystemTime,
+    pub window_length: Duration,
+    pub window_start: SystemTime,
+    pub window_count: u64,
+    pub window_pages: u64,
+}
+
+/// A snapshot of how far along a job is
+#[derive(Clone, Debug, Serialize)]
+pub struct ProgressEvent {
+    pub bytes: u64,
+    pub total_bytes: u64,
+    pub percent: f64,
+    pub pages: u64,
+    /// Rate over the last window
+    pub bytes_per_sec: f64,
+    /// Rate since the start
+    pub bytes_per_sec_avg: f64,
+    /// Rate over the last window
+    pub pages_per_sec: f64,
+    pub elapsed_secs: f64,
+    /// Estimated time remaining, based on the rate over the last window
+    pub eta_secs: f64,
+    /// Number of items waiting in each of the pipeline's queues
+    pub queue_depths: BTreeMap<&'static str, usize>,
+}
+
+impl Progress {
+    pub fn new(total: u64) -> Self {
+        let now = SystemTime::now();
+        Self {
+            total,
+            start: now,
+            window_length: Duration::from_secs(5),
+            window_start: now,
+            window_count: 0,
+            window_pages: 0,
+        }
+    }
+
+    pub fn event(&mut self, count: u64, pages: u64, now: SystemTime) -> ProgressEvent {
+        let percent = 100.0 * (count as f64) / (self.total as f64);
+
+        let window_elapsed = now.duration_since(self.window_start).unwrap();
+        let rate = (count - self.window_count) as f64 / window_elapsed.as_secs_f64();
+        let pages_rate = (pages - self.window_pages) as f64 / window_elapsed.as_secs_f64();
+
+        let elapsed = now.duration_since(self.start).unwrap();
+        let rate_avg = count as f64 / elapsed.as_secs_f64();
+
+        let eta_secs = (self.total.saturating_sub(count)) as f64 / rate;
+
+        if window_elapsed > self.window_length {
+            self.window_start = now;
+            self.window_count = count;
+            self.window_pages = pages;
+        }
+
+        ProgressEvent {
+            bytes: count,
+            total_bytes: self.total,
+            percent,
+            pages,
+            bytes_per_sec: rate,
+            bytes_per_sec_avg: rate_avg,
+            pages_per_sec: pages_rate,
+            elapsed_secs: elapsed.as_secs_f64(),
+            eta_secs,
+            queue_depths: BTreeMap::new(),
+        }
+    }
+}
+
+impl ProgressEvent {
+    pub fn with_queue_depths(
+        mut self,
+        queue_depths: impl IntoIterator<Item = (&'static str, usize)>,
+    ) -> Self {
+        self.queue_depths.extend(queue_depths);
+        self
+    }
+
+    /// Human readable one line summary
+    pub fn summary(&self) -> String {
+        let eta_total_secs = self.eta_secs + self.elapsed_secs;
+
+        let mut ret = format!(
+            "{:.2}% {}/{} | {:.2} MB/s ({:.2} MB/s) | {} pages ({:.0} pages/s) | ETA {} mins ({} mins total)",
+            self.percent,
+            self.bytes,
+            self.total_bytes,
+            self.bytes_per_sec / BYTES_PER_MB,
+            self.bytes_per_sec_avg / BYTES_PER_MB,
+            self.pages,
+            self.pages_per_sec,
+            format_mins(self.eta_secs),
+            format_mins(eta_total_secs),
+        );
+
+        if !self.queue_depths.is_empty() {
+            let depths: Vec<_> = self
+                .queue_depths
+                .iter()
+                .map(|(name, depth)| format!("{}: {}", name, depth))
+                .collect();
+            ret += &format!(" | queues {}", depths.join(", "));
+        }
+
+        ret
+    }
+}
+
+fn format_mins(secs: f64) -> String {
+    if !secs.is_finite() {
+        return "--:--".to_string();
+    }
+    format!(
+        "{:02.}:{:02.}",
+        (secs / 60.0).floor(),
+        (secs % 60.0).floor()
+    )
+}
+
+/// Receives progress events and shows them to the user
+pub trait ProgressReporter: Send {
+    /// Called periodically while the job is running
+    fn report(&mut self, event: &ProgressEvent);
+    /// Called once when the job is done
+    fn finish(&mut self, event: &ProgressEvent);
+}
+
+/// How progress is reported. Selectable from the command line.
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ProgressFormat {
+    /// A single line redrawn in place. Best for interactive terminals.
+    #[default]
+    Tty,
+    /// Plain log lines at a fixed interval. Best for CI logs and batch schedulers.
+    Log,
+    /// One JSON object per line, for every event, on stderr or in the progress file.
+    Json,
+}
+
+impl ProgressFormat {
+    /// `interval` is the time between log lines for [ProgressFormat::Log], and `file` where
+    /// [ProgressFormat::Json] events are written instead of stderr
+    pub fn reporter(
+        self,
+        interval: Duration,
+        file: Option<&Path>,
+    ) -> std::io::Result<Box<dyn ProgressReporter>> {
+        Ok(match self {
+            ProgressFormat::Tty => Box::new(TtyReporter::new(std::io::stdout())),
+            ProgressFormat::Log => Box::new(LogReporter::new(std::io::stdout(), interval)),
+            ProgressFormat::Json => match file {
+                Some(file) => Box::new(JsonReporter::new(BufWriter::new(File::create(file)?))),
+                None => Box::new(JsonReporter::new(std::io::stderr())),
+            },
+        })
+    }
+}
+
+/// Redraws a single line on the terminal using `\r`
+pub struct TtyReporter<W: Write + Send> {
+    writer: W,
+}
+
+impl<W: Write + Send> TtyReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: Write + Send> ProgressReporter for TtyReporter<W> {
+    fn report(&mut self, event: &ProgressEvent) {
+        let _ = write!(self.writer, "Progress: {} \r", event.summary());
+        let _ = self.writer.flush();
+    }
+
+    fn finish(&mut self, event: &ProgressEvent) {
+        let _ = writeln!(self.writer, "Progress: {} ", event.summary());
+        let _ = self.writer.flush();
+    }
+}
+
+/// Prints a plain line at most once per interval
+pub struct LogReporter<W: Write + Send> {
+    writer: W,
+    interval: Duration,
+    last_report: Option<SystemTime>,
+}
+
+impl<W: Write + Send> LogReporter<W> {
+    pub fn new(writer: W, interval: Duration) -> Self {
+        Self {
+            writer,
+            interval,
+            last_report: None,
+        }
+    }
+}
+
+impl<W: Write + Send> ProgressReporter for LogReporter<W> {
+    fn report(&mut self, event: &ProgressEvent) {
+        let now = SystemTime::now();
+        let due = match self.last_report {
+            None => true,
+            Some(last) => now.duration_since(last).unwrap_or_default() >= self.interval,
+        };
+        if due {
+            self.last_report = Some(now);
+            let _ = writeln!(
+                self.writer,
+                "[{:.0}s] Progress: {}",
+                event.elapsed_secs,
+                event.summary()
+            );
+            let _ = self.writer.flush();
+        }
+    }
+
+    fn finish(&mut self, event: &ProgressEvent) {
+        let _ = writeln!(
+            self.writer,
+            "[{:.0}s] Done: {}",
+            event.elapsed_secs,
+            event.summary()
+        );
+        let _ = self.writer.flush();
+    }
+}
+
+/// Writes every event as a JSON object on its own line
+pub struct JsonReporter<W: Write + Send> {
+    writer: W,
+}
+
+impl<W: Write + Send> JsonReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    fn write_event(&mut self, kind: &str, event: &ProgressEvent) {
+        #[derive(Serialize)]
+        struct Line<'a> {
+            event: &'a str,
+            #[serde(flatten)]
+            progress: &'a ProgressEvent,
+        }
+
+        let line = Line {
+            event: kind,
+            progress: event,
+        };
+        let _ = serde_json::to_writer(&mut self.writer, &line);
+        let _ = self.writer.write_all(b"\n");
+        let _ = self.writer.flush();
+    }
+}
+
+impl<W: Write + Send> ProgressReporter for JsonReporter<W> {
+    fn report(&mut self, event: &ProgressEvent) {
+        self.write_event("progress", event);
+    }
+
+    fn finish(&mut self, event: &ProgressEvent) {
+        self.write_event("done", event);
+    }
+}
+
+pub fn progress(
+    count: u64,
+    total: u64,
+    rate_divider: f64,
+    rate_unit: &str,
+    start: SystemTime,
+    now: SystemTime,
+) -> String {
+    let percent = 100.0 * (count as f64) / (total as f64);
+
+    let elapsed = now.duration_since(start).unwrap();
+    let rate = count as f64 / elapsed.as_secs_f64();
+
+    let eta_remaining_secs = (total - count) as f64 / rate;
+    let eta_remaining_mins_part = (eta_remaining_secs / 60.0).floor();
+    let eta_remaining_secs_part = (eta_remaining_secs % 60.0).floor();
+
+    let eta_total_secs = total as f64 / rate;
+    let eta_total_mins_part = (eta_total_secs / 60.0).floor();
+    let eta_total_secs_part = (eta_total_secs % 60.0).floor();
+
+    let rate = rate / rate_divider;
+
+    format!(
+        "{:.2}% {}/{} | {:.2} {} | ETA {:02.}:{:02.} mins ({:02.}:{:02.} mins total)",
+        percent,
+        count,
+        total,
+        rate,
+        rate_unit,
+        eta_remaining_mins_part,
+        eta_remaining_secs_part,
+        eta_total_mins_part,
+        eta_total_secs_part,
+    )
+}
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use super::{
+    JsonReporter, LogReporter, Progress, ProgressEvent, ProgressFormat, ProgressReporter,
+    TtyReporter,
+};
+
+// 250 of 1000 bytes and 10 pages, two seconds in
+fn event() -> ProgressEvent {
+    let mut progress = Progress::new(1000);
+    let now = progress.start + Duration::from_secs(2);
+    progress
+        .event(250, 10, now)
+        .with_queue_depths([("parser", 3), ("writer", 1)])
+}
+
+#[test]
+fn event_rates() {
+    let event = event();
+    assert_eq!(event.percent, 25.0);
+    assert_eq!(event.bytes_per_sec, 125.0);
+    assert_eq!(event.bytes_per_sec_avg, 125.0);
+    assert_eq!(event.pages_per_sec, 5.0);
+    assert_eq!(event.eta_secs, 6.0);
+    assert!(event.summary().ends_with("| queues parser: 3, writer: 1"));
+}
+
+#[test]
+fn json_reporter() {
+    let mut out = Vec::new();
+    let mut reporter = JsonReporter::new(&mut out);
+    reporter.report(&event());
+    reporter.report(&event());
+    reporter.finish(&event());
+
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<serde_json::Value> = out
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 3);
+    for line in &lines {
+        assert_eq!(line["bytes"], 250);
+        assert_eq!(line["total_bytes"], 1000);
+        assert_eq!(line["percent"], 25.0);
+        assert_eq!(line["pages"], 10);
+        assert_eq!(line["bytes_per_sec"], 125.0);
+        assert_eq!(line["pages_per_sec"], 5.0);
+        assert_eq!(line["elapsed_secs"], 2.0);
+        assert_eq!(line["eta_secs"], 6.0);
+        assert_eq!(line["queue_depths"]["parser"], 3);
+        assert_eq!(line["queue_depths"]["writer"], 1);
+    }
+    assert_eq!(lines[0]["event"], "progress");
+    assert_eq!(lines[2]["event"], "done");
+}
+
+#[test]
+fn json_reporter_file() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    let mut reporter = ProgressFormat::Json
+        .reporter(Duration::from_secs(1), Some(file.path()))
+        .unwrap();
+    reporter.report(&event());
+    reporter.finish(&event());
+    drop(reporter);
+
+    let out = std::fs::read_to_string(file.path()).unwrap();
+    let events: Vec<_> = out
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["event"].clone())
+        .collect();
+    assert_eq!(events, vec!["progress", "done"]);
+}
+
+#[test]
+fn log_reporter() {
+    let mut out = Vec::new();
+    let mut reporter = LogReporter::new(&mut out, Duration::from_secs(3600));
+    reporter.report(&event());
+    // Within the interval
+    reporter.report(&event());
+    reporter.finish(&event());
+
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<_> = out.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("[2s] Progress: 25.00% 250/1000"));
+    assert!(lines[1].starts_with("[2s] Done: 25.00% 250/1000"));
+}
+
+#[test]
+fn tty_reporter() {
+    let mut out = Vec::new();
+    let mut reporter = TtyReporter::new(&mut out);
+    reporter.report(&event());
+    reporter.finish(&event());
+
+    let out = String::from_utf8(out).unwrap();
+    let summary = event().summary();
+    assert_eq!(
+        out,
+        format!("Progress: {} \rProgress: {} \n", summary, summary)
+    );
+}
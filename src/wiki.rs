@@ -1,11 +1,15 @@
-//! This module is not used as of now, I found this info and noted this here in case it's useful in
-//! the future.
+//! Namespaces, interwiki prefixes and language codes, used to tell what a link points at.
 
 // Reference:
 // - https://en.wikipedia.org/wiki/Wikipedia:Administration#Data_structure_and_development
 // - https://en.wikipedia.org/wiki/Wikipedia:Namespace
 // - (Aliases, pseudo-namespaces) https://en.wikipedia.org/wiki/Wikipedia:Shortcut#List_of_prefixes
-pub const NAMESPACES: [&'static str; 25] = [
+
+use std::sync::OnceLock;
+
+use html_escape::decode_html_entities;
+
+pub const NAMESPACES: [&str; 25] = [
     // Subject namespaces
     // "(Main/Article)" // This namespace is implicit and omitted in the URL.
     "Talk",
@@ -51,3 +55,451 @@ pub const PSEUDO_NAMESPACES: [&str; 4] = [
     // Pseudo-namespaces
     "CAT", "H", "MOS", "P",
 ];
+
+// Prefixes linking to sister projects and other wikis
+// Reference: https://meta.wikimedia.org/wiki/Interwiki_map
+pub const INTERWIKI_PREFIXES: [&str; 34] = [
+    "wikipedia",
+    "w",
+    "wiktionary",
+    "wikt",
+    "commons",
+    "c",
+    "meta",
+    "m",
+    "wikisource",
+    "s",
+    "wikiquote",
+    "q",
+    "wikibooks",
+    "b",
+    "wikinews",
+    "n",
+    "wikiversity",
+    "v",
+    "wikivoyage",
+    "voy",
+    "wikispecies",
+    "species",
+    "wikidata",
+    "d",
+    "mediawikiwiki",
+    "mw",
+    "wikimedia",
+    "foundation",
+    "wmf",
+    "phabricator",
+    "phab",
+    "incubator",
+    "outreach",
+    "doi",
+];
+
+// Language codes of the Wikipedia editions, used as interlanguage link prefixes
+// Reference: https://meta.wikimedia.org/wiki/List_of_Wikipedias
+pub const LANGUAGE_CODES: &[&str] = &[
+    "aa",
+    "ab",
+    "ace",
+    "ady",
+    "af",
+    "ak",
+    "als",
+    "alt",
+    "am",
+    "ami",
+    "an",
+    "ang",
+    "anp",
+    "ar",
+    "arc",
+    "ary",
+    "arz",
+    "as",
+    "ast",
+    "atj",
+    "av",
+    "avk",
+    "awa",
+    "ay",
+    "az",
+    "azb",
+    "ba",
+    "ban",
+    "bar",
+    "bat-smg",
+    "bbc",
+    "bcl",
+    "be",
+    "be-tarask",
+    "be-x-old",
+    "bg",
+    "bh",
+    "bi",
+    "bjn",
+    "blk",
+    "bm",
+    "bn",
+    "bo",
+    "bpy",
+    "br",
+    "bs",
+    "bug",
+    "bxr",
+    "ca",
+    "cbk-zam",
+    "cdo",
+    "ce",
+    "ceb",
+    "ch",
+    "cho",
+    "chr",
+    "chy",
+    "ckb",
+    "co",
+    "cr",
+    "crh",
+    "cs",
+    "csb",
+    "cu",
+    "cv",
+    "cy",
+    "da",
+    "dag",
+    "de",
+    "din",
+    "diq",
+    "dsb",
+    "dty",
+    "dv",
+    "dz",
+    "ee",
+    "el",
+    "eml",
+    "en",
+    "eo",
+    "es",
+    "et",
+    "eu",
+    "ext",
+    "fa",
+    "fat",
+    "ff",
+    "fi",
+    "fiu-vro",
+    "fj",
+    "fo",
+    "fon",
+    "fr",
+    "frp",
+    "frr",
+    "fur",
+    "fy",
+    "ga",
+    "gag",
+    "gan",
+    "gcr",
+    "gd",
+    "gl",
+    "glk",
+    "gn",
+    "gom",
+    "gor",
+    "got",
+    "gpe",
+    "gsw",
+    "gu",
+    "guc",
+    "gur",
+    "guw",
+    "gv",
+    "ha",
+    "hak",
+    "haw",
+    "he",
+    "hi",
+    "hif",
+    "ho",
+    "hr",
+    "hsb",
+    "ht",
+    "hu",
+    "hy",
+    "hyw",
+    "hz",
+    "ia",
+    "id",
+    "ie",
+    "ig",
+    "ii",
+    "ik",
+    "ilo",
+    "inh",
+    "io",
+    "is",
+    "it",
+    "iu",
+    "ja",
+    "jam",
+    "jbo",
+    "jv",
+    "ka",
+    "kaa",
+    "kab",
+    "kbd",
+    "kbp",
+    "kcg",
+    "kg",
+    "ki",
+    "kj",
+    "kk",
+    "kl",
+    "km",
+    "kn",
+    "ko",
+    "koi",
+    "kr",
+    "krc",
+    "ks",
+    "ksh",
+    "ku",
+    "kv",
+    "kw",
+    "ky",
+    "la",
+    "lad",
+    "lb",
+    "lbe",
+    "lez",
+    "lfn",
+    "lg",
+    "li",
+    "lij",
+    "lld",
+    "lmo",
+    "ln",
+    "lo",
+    "lrc",
+    "lt",
+    "ltg",
+    "lv",
+    "mad",
+    "mai",
+    "map-bms",
+    "mdf",
+    "mg",
+    "mh",
+    "mhr",
+    "mi",
+    "min",
+    "mk",
+    "ml",
+    "mn",
+    "mni",
+    "mnw",
+    "mo",
+    "mr",
+    "mrj",
+    "ms",
+    "mt",
+    "mus",
+    "mwl",
+    "my",
+    "myv",
+    "mzn",
+    "na",
+    "nah",
+    "nap",
+    "nds",
+    "nds-nl",
+    "ne",
+    "new",
+    "ng",
+    "nia",
+    "nl",
+    "nn",
+    "no",
+    "nov",
+    "nqo",
+    "nrm",
+    "nso",
+    "nv",
+    "ny",
+    "oc",
+    "olo",
+    "om",
+    "or",
+    "os",
+    "pa",
+    "pag",
+    "pam",
+    "pap",
+    "pcd",
+    "pcm",
+    "pdc",
+    "pfl",
+    "pi",
+    "pih",
+    "pl",
+    "pms",
+    "pnb",
+    "pnt",
+    "ps",
+    "pt",
+    "pwn",
+    "qu",
+    "rm",
+    "rmy",
+    "rn",
+    "ro",
+    "roa-rup",
+    "roa-tara",
+    "ru",
+    "rue",
+    "rw",
+    "sa",
+    "sah",
+    "sat",
+    "sc",
+    "scn",
+    "sco",
+    "sd",
+    "se",
+    "sg",
+    "sh",
+    "shi",
+    "shn",
+    "si",
+    "simple",
+    "sk",
+    "skr",
+    "sl",
+    "sm",
+    "smn",
+    "sn",
+    "so",
+    "sq",
+    "sr",
+    "srn",
+    "ss",
+    "st",
+    "stq",
+    "su",
+    "sv",
+    "sw",
+    "szl",
+    "szy",
+    "ta",
+    "tay",
+    "tcy",
+    "te",
+    "tet",
+    "tg",
+    "th",
+    "ti",
+    "tk",
+    "tl",
+    "tly",
+    "tn",
+    "to",
+    "tpi",
+    "tr",
+    "trv",
+    "ts",
+    "tt",
+    "tum",
+    "tw",
+    "ty",
+    "tyv",
+    "udm",
+    "ug",
+    "uk",
+    "ur",
+    "uz",
+    "ve",
+    "vec",
+    "vep",
+    "vi",
+    "vls",
+    "vo",
+    "wa",
+    "war",
+    "wo",
+    "wuu",
+    "xal",
+    "xh",
+    "xmf",
+    "yi",
+    "yo",
+    "yue",
+    "za",
+    "zea",
+    "zgh",
+    "zh",
+    "zh-classical",
+    "zh-min-nan",
+    "zh-yue",
+    "zu",
+];
+
+/// Split `prefix:rest` into (prefix, rest) if the prefix matches one of `prefixes`, ignoring case
+/// and treating underscores as spaces
+pub fn split_prefix<'a>(
+    title: &'a str,
+    prefixes: &[&'static str],
+) -> Option<(&'static str, &'a str)> {
+    let (prefix, rest) = title.split_once(':')?;
+    let prefix = prefix.trim().replace('_', " ");
+    prefixes
+        .iter()
+        .find(|p| p.eq_ignore_ascii_case(&prefix))
+        .map(|p| (*p, rest))
+}
+
+/// Same as [split_prefix] for interwiki prefixes and language codes, which only match as written
+/// (in lowercase), so titles like `S: The Story` stay titles
+pub fn split_project_prefix<'a>(
+    title: &'a str,
+    prefixes: &[&'static str],
+) -> Option<(&'static str, &'a str)> {
+    let (prefix, rest) = title.split_once(':')?;
+    let prefix = prefix.trim();
+    prefixes.iter().find(|p| **p == prefix).map(|p| (*p, rest))
+}
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+static LANGUAGE: OnceLock<String> = OnceLock::new();
+
+/// Set the language code of the wiki being processed, once at startup. Links prefixed with it,
+/// like `[[en:Paris]]` on the English Wikipedia, point at the wiki itself. Returns false if the
+/// language was already set.
+pub fn set_language(code: &str) -> bool {
+    LANGUAGE.set(code.to_owned()).is_ok()
+}
+
+/// Language code of the wiki being processed, [DEFAULT_LANGUAGE] unless [set_language] was called
+pub fn language() -> &'static str {
+    LANGUAGE.get().map_or(DEFAULT_LANGUAGE, String::as_str)
+}
+
+/// Resolve a link target to the title of the page it points at: decode entities, drop the
+/// section and a prefix of the wiki's own language, turn underscores into spaces and capitalize
+/// the first letter like MediaWiki does
+pub fn normalize_title(target: &str) -> String {
+    let target = decode_html_entities(target);
+    let target = match target.split_once('#') {
+        Some((page, _)) => page,
+        None => &target,
+    };
+    // `[[en:Paris]]` on the English Wikipedia links to Paris
+    let target = split_project_prefix(target, &[language()]).map_or(target, |(_, rest)| rest);
+    let target = target.replace('_', " ");
+    let target = target.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut chars = target.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -1,99 +1,217 @@
 use rayon::iter::{ParallelBridge, ParallelIterator};
 use std::fs::File;
 use std::io::{BufWriter, Write};
-use std::sync::mpsc;
+use std::sync::Arc;
 use std::thread::JoinHandle;
+use std::time::Instant;
 
-const QUEUE_SIZE: usize = 1024;
+use crate::byte_channel;
+use crate::metrics::{Channel, Metrics};
+use crate::page_guard::PageGuard;
 
 const K: usize = 1024;
 const M: usize = 1024 * K;
-const G: usize = 1024 * M;
-const OUTPUT_BUFFER_SIZE: usize = 2 * G;
-const INDEX_BUFFER_SIZE: usize = 1 * G;
+
+/// Memory budgets for the queues and buffers of a [WorkQueue](self::WorkQueue), in bytes.
+///
+/// Peak memory of the pipeline is bounded by
+/// `parser_queue + writer_queue + data_buffer + index_buffer`, plus, for each worker thread, the
+/// page it is working on, its output, and the extractor's working memory for that page.
+#[derive(Clone, Copy, Debug)]
+pub struct WorkQueueConfig {
+    /// Wikitext waiting to be processed by the extractor
+    pub parser_queue: usize,
+    /// Extractor output waiting to be written
+    pub writer_queue: usize,
+    /// Buffer in front of the data file
+    pub data_buffer: usize,
+    /// Buffer in front of the index file
+    pub index_buffer: usize,
+}
+
+impl Default for WorkQueueConfig {
+    fn default() -> Self {
+        Self {
+            parser_queue: 256 * M,
+            writer_queue: 256 * M,
+            data_buffer: 64 * M,
+            index_buffer: 16 * M,
+        }
+    }
+}
+
+impl WorkQueueConfig {
+    /// Memory held by the queues and buffers when they are full
+    pub fn budget(&self) -> usize {
+        self.parser_queue + self.writer_queue + self.data_buffer + self.index_buffer
+    }
+}
 
 pub struct WorkQueue {
-    parser_sender: Option<mpsc::SyncSender<(String, Vec<u8>)>>,
+    parser_sender: Option<byte_channel::Sender<(String, Vec<u8>)>>,
+    writer_sender: byte_channel::Sender<(String, String)>,
     parser_thread: JoinHandle<()>,
     writer_thread: JoinHandle<()>,
+    metrics: Arc<Metrics>,
+    page_guard: Arc<PageGuard>,
 }
 
 impl WorkQueue {
-    pub fn new<F>(data_file: String, index_file: String, text_processor: F) -> Self
+    /// `fallback_processor` is used instead of `text_processor` for pages that exceed the limits
+    /// of `page_guard`.
+    pub fn new<F, G>(
+        data_file: String,
+        index_file: String,
+        text_processor: F,
+        fallback_processor: G,
+        page_guard: PageGuard,
+        metrics: Arc<Metrics>,
+        config: WorkQueueConfig,
+    ) -> Self
     where
         F: Fn(&[u8], &str) -> String + Sync + Send + 'static,
+        G: Fn(&[u8], &str) -> String + Sync + Send + 'static,
     {
-        let (writer_sender, writer_receiver) = mpsc::sync_channel::<(String, String)>(QUEUE_SIZE);
-        let (parser_sender, parser_receiver) = mpsc::sync_channel::<(String, Vec<u8>)>(QUEUE_SIZE);
+        let page_guard = Arc::new(page_guard);
+
+        let (writer_sender, writer_receiver) =
+            byte_channel::channel::<(String, String)>(config.writer_queue);
+        let (parser_sender, parser_receiver) =
+            byte_channel::channel::<(String, Vec<u8>)>(config.parser_queue);
 
         // Start the writer thread
-        let writer_thread =
-            std::thread::spawn(move || file_writer(data_file, index_file, writer_receiver));
+        let writer_thread = {
+            let metrics = metrics.clone();
+            std::thread::spawn(move || {
+                file_writer(data_file, index_file, writer_receiver, metrics, config)
+            })
+        };
 
         // Iterate over the elements in the parser channel parallely, and run text_processor in a
         // thread pool. Send the result over to the writer thread.
-        let parser_thread = std::thread::spawn(move || {
-            parser_receiver.into_iter().par_bridge().for_each_with(
-                writer_sender,
-                |writer_sender, (title, contents)| {
-                    // Process the text
-                    let text = (text_processor)(&contents, &title);
-
-                    // Send the output to the writer thread
-                    writer_sender.send((title, text)).unwrap();
-                },
-            )
-        });
+        let parser_thread = {
+            let writer_sender = writer_sender.clone();
+            let metrics = metrics.clone();
+            let page_guard = page_guard.clone();
+            std::thread::spawn(move || {
+                let pages = {
+                    let metrics = metrics.clone();
+                    std::iter::from_fn(move || {
+                        let start = Instant::now();
+                        let page = parser_receiver.recv().ok();
+                        metrics.blocked(Channel::ParserRecv, start.elapsed());
+                        page
+                    })
+                };
+                pages.par_bridge().for_each_with(
+                    writer_sender,
+                    |writer_sender, (title, contents)| {
+                        // Process the text
+                        let start = Instant::now();
+                        let text = page_guard.run(
+                            &contents,
+                            &title,
+                            &text_processor,
+                            &fallback_processor,
+                            &metrics,
+                        );
+                        metrics.extracted(&title, start.elapsed());
+
+                        let Some(text) = text else {
+                            return;
+                        };
+
+                        // Send the output to the writer thread
+                        let start = Instant::now();
+                        let size = title.len() + text.len();
+                        writer_sender.send((title, text), size).unwrap();
+                        metrics.blocked(Channel::WriterSend, start.elapsed());
+                    },
+                )
+            })
+        };
 
         Self {
             parser_sender: Some(parser_sender),
+            writer_sender,
             parser_thread,
             writer_thread,
+            metrics,
+            page_guard,
         }
     }
 
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    /// Number of pages waiting to be processed, and number of outputs waiting to be written
+    pub fn queue_depths(&self) -> [(&'static str, usize); 2] {
+        [
+            (
+                "parser",
+                self.parser_sender.as_ref().map_or(0, |s| s.depth()),
+            ),
+            ("writer", self.writer_sender.depth()),
+        ]
+    }
+
     pub fn queue(&mut self, text: Vec<u8>, title: String) {
+        self.metrics.bytes_in(text.len());
+
+        let start = Instant::now();
+        let size = title.len() + text.len();
         self.parser_sender
             .as_ref()
             .unwrap()
-            .send((title, text))
+            .send((title, text), size)
             .unwrap();
+        self.metrics.blocked(Channel::ParserSend, start.elapsed());
     }
 
     pub fn wait_for_completion(mut self) {
         drop(self.parser_sender.take());
+        drop(self.writer_sender);
         self.parser_thread.join().unwrap();
         self.writer_thread.join().unwrap();
+        self.page_guard.flush().unwrap();
     }
 }
 
-fn file_writer(data_file: String, index_file: String, rx: mpsc::Receiver<(String, String)>) {
+fn file_writer(
+    data_file: String,
+    index_file: String,
+    rx: byte_channel::Receiver<(String, String)>,
+    metrics: Arc<Metrics>,
+    config: WorkQueueConfig,
+) {
     let data_file = File::create(data_file).unwrap();
-    let mut data_file_writer = BufWriter::with_capacity(OUTPUT_BUFFER_SIZE, data_file);
+    let mut data_file_writer = BufWriter::with_capacity(config.data_buffer, data_file);
 
     let index_file = File::create(index_file).unwrap();
-    let mut index_file_writer = BufWriter::with_capacity(INDEX_BUFFER_SIZE, index_file);
+    let mut index_file_writer = BufWriter::with_capacity(config.index_buffer, index_file);
 
     let mut pos = 0;
 
     loop {
+        let start = Instant::now();
         let (name, contents) = match rx.recv() {
             Err(_) => break,
             Ok(x) => x,
         };
+        metrics.blocked(Channel::WriterRecv, start.elapsed());
 
+        // Outputs can be larger than the buffer, so write() may not take them in one go
         let bytes = contents.as_bytes();
-        let bytes_written = data_file_writer.write(bytes).unwrap();
-
-        // This should be the case. Just in case this assumption is wrong, do an early exit.
-        assert!(bytes_written == bytes.len());
+        data_file_writer.write_all(bytes).unwrap();
+        let bytes_written = bytes.len();
 
-        let written = index_file_writer
-            .write(format!("{}: {}\n", pos, name).as_bytes())
+        index_file_writer
+            .write_all(format!("{}: {}\n", pos, name).as_bytes())
             .unwrap();
-        assert!(written != 0);
 
         pos += bytes_written;
+        metrics.bytes_out(bytes_written);
     }
 
     data_file_writer.flush().unwrap();
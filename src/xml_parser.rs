@@ -1,6 +1,5 @@
 use std::io::BufRead;
-use std::str;
-use std::time::Duration;
+use std::sync::Arc;
 use std::time::SystemTime;
 
 use quick_xml::events::Event;
@@ -9,47 +8,122 @@ use quick_xml::reader::Reader;
 use quick_xml::Error;
 use quick_xml::Result;
 
-use crate::progress::Progress;
+use crate::extractors::disambiguation;
+use crate::metrics::{Metrics, SkipReason};
+use crate::progress::{Progress, ProgressReporter};
 use crate::work_queue::WorkQueue;
 
+/// Namespaces of technical pages about Wikipedia itself, skipped by default
+pub const SKIPPED_NAMESPACES: &[&str] = &[
+    "Wikipedia",
+    "Portal",
+    "File",
+    "Template",
+    "Category",
+    "Draft",
+    "Module",
+    "MediaWiki",
+    "Help",
+];
+
+/// Which pages are handed to the extractor. Redirects are always skipped.
+#[derive(Clone, Debug)]
+pub struct PageFilter {
+    /// Pages whose title starts with one of these namespaces are skipped
+    pub skip_namespaces: Vec<&'static str>,
+    pub disambiguation: DisambiguationPages,
+}
+
+/// What to do with disambiguation pages, recognized by their title or their templates
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisambiguationPages {
+    #[default]
+    Skip,
+    /// Skip every other page
+    Only,
+}
+
+impl Default for PageFilter {
+    fn default() -> Self {
+        Self {
+            skip_namespaces: SKIPPED_NAMESPACES.to_vec(),
+            disambiguation: DisambiguationPages::Skip,
+        }
+    }
+}
+
+impl PageFilter {
+    /// The default filter, letting pages of `namespace` through
+    pub fn including(namespace: &str) -> Self {
+        let mut filter = Self::default();
+        filter.skip_namespaces.retain(|ns| *ns != namespace);
+        filter
+    }
+
+    fn skip_reason(&self, title: &str, text: &[u8]) -> Option<SkipReason> {
+        let skip_namespace = self.skip_namespaces.iter().any(|ns| {
+            title
+                .strip_prefix(ns)
+                .is_some_and(|rest| rest.starts_with(':'))
+        });
+        if skip_namespace {
+            return Some(SkipReason::Namespace);
+        }
+
+        // Checking the title is cheaper than looking for the templates
+        let disambiguation = disambiguation::is_disambiguation_title(title)
+            || disambiguation::is_disambiguation(text);
+        match (self.disambiguation, disambiguation) {
+            (DisambiguationPages::Skip, true) => Some(SkipReason::Disambiguation),
+            (DisambiguationPages::Only, false) => Some(SkipReason::NotDisambiguation),
+            _ => None,
+        }
+    }
+}
+
 pub struct XMLParser<R: BufRead> {
     reader: Reader<R>,
     file_size: u64, // for tracking progress
     work_queue: WorkQueue,
+    progress_reporter: Box<dyn ProgressReporter>,
+    pages_queued: u64,
+    metrics: Arc<Metrics>,
+    page_filter: PageFilter,
 }
 
 impl<R: BufRead> XMLParser<R> {
-    pub fn new<F>(
-        data_file: String,
-        index_file: String,
-        text_processor: F,
+    pub fn new(
+        work_queue: WorkQueue,
         reader: R,
         file_size: u64,
-    ) -> Result<Self>
-    where
-        F: Fn(&[u8], &str) -> String + Clone + Sync + Send + Copy + 'static,
-    {
+        progress_reporter: Box<dyn ProgressReporter>,
+    ) -> Result<Self> {
         let reader = Reader::from_reader(reader);
-        let work_queue = WorkQueue::new(data_file, index_file, text_processor);
+        let metrics = work_queue.metrics();
 
         Ok(Self {
             reader,
             file_size,
             work_queue,
+            progress_reporter,
+            pages_queued: 0,
+            metrics,
+            page_filter: PageFilter::default(),
         })
     }
 
+    pub fn with_page_filter(mut self, page_filter: PageFilter) -> Self {
+        self.page_filter = page_filter;
+        self
+    }
+
     // Main XML parsing function
     pub fn parse_xml(mut self) -> Result<()> {
         let mut buffer = Vec::new();
         match self.reader.read_event_into(&mut buffer) {
             Err(e) => self.terminate(e),
-            Ok(Event::Start(e)) => {
-                if e.name().into_inner() == b"mediawiki" {
-                    self.parse_mediawiki()?;
-                } else {
-                    return Err(Error::TextNotFound);
-                }
+            Ok(Event::Start(e)) if e.name().into_inner() == b"mediawiki" => {
+                self.parse_mediawiki()?;
             }
             _ => return Err(Error::TextNotFound),
         };
@@ -63,24 +137,17 @@ impl<R: BufRead> XMLParser<R> {
         let mut garbage = Vec::new();
         let file_size = self.file_size;
 
-        let mut progress = Progress {
-            total: file_size,
-            rate_divider: 1024.0 * 1024.0,
-            rate_unit: "MB/s",
-            start: SystemTime::now(),
-            window_length: Duration::from_secs(5),
-            window_start: SystemTime::now(),
-            window_count: 0,
-        };
+        let mut progress = Progress::new(file_size);
 
         let mut last_pos = 0;
         loop {
             let pos = self.reader.buffer_position();
             if (pos - last_pos) > 1024 * 1024 * 100 {
                 last_pos = pos;
-                let progress_str = progress.progress(pos as _, SystemTime::now());
-
-                print!("Progress: {} \r", progress_str);
+                let event = progress
+                    .event(pos as _, self.pages_queued, SystemTime::now())
+                    .with_queue_depths(self.work_queue.queue_depths());
+                self.progress_reporter.report(&event);
             }
 
             buffer.clear();
@@ -105,7 +172,14 @@ impl<R: BufRead> XMLParser<R> {
             }
         }
 
-        println!();
+        let event = progress
+            .event(
+                self.reader.buffer_position() as _,
+                self.pages_queued,
+                SystemTime::now(),
+            )
+            .with_queue_depths(self.work_queue.queue_depths());
+        self.progress_reporter.finish(&event);
 
         Ok(())
     }
@@ -114,6 +188,8 @@ impl<R: BufRead> XMLParser<R> {
         let mut title = Vec::new();
         let mut text = Vec::new();
 
+        self.metrics.page_parsed();
+
         // Parse the page
         loop {
             match self.reader.read_event_into(buffer) {
@@ -122,6 +198,7 @@ impl<R: BufRead> XMLParser<R> {
                     let tag = e.name().into_inner();
                     if tag == b"redirect" {
                         // We don't care about redirect pages
+                        self.metrics.page_skipped(SkipReason::Redirect);
                         self.reader.read_to_end_into(QName(b"page"), garbage)?;
                         return Ok(());
                     }
@@ -144,10 +221,8 @@ impl<R: BufRead> XMLParser<R> {
                         }
                     }
                 }
-                Ok(Event::End(e)) => {
-                    if e.name().into_inner() == b"page" {
-                        break;
-                    }
+                Ok(Event::End(e)) if e.name().into_inner() == b"page" => {
+                    break;
                 }
                 Ok(Event::Eof) => break,
                 _ => (),
@@ -156,21 +231,13 @@ impl<R: BufRead> XMLParser<R> {
 
         // Skip technical articles about Wikipedia itself
         let title = String::from_utf8(title)?;
-        if title.starts_with("Wikipedia:")
-            || title.starts_with("Portal:")
-            || title.starts_with("File:")
-            || title.starts_with("Template:")
-            || title.starts_with("Category:")
-            || title.starts_with("Draft:")
-            || title.starts_with("Module:")
-            || title.starts_with("MediaWiki:")
-            || title.starts_with("Help:")
-            || title.to_lowercase().ends_with("(disambiguation)")
-        {
+        if let Some(reason) = self.page_filter.skip_reason(&title, &text) {
+            self.metrics.page_skipped(reason);
             return Ok(());
         }
 
         self.work_queue.queue(text, title);
+        self.pages_queued += 1;
 
         Ok(())
     }
@@ -204,10 +271,8 @@ impl<R: BufRead> XMLParser<R> {
                         }
                     }
                 }
-                Ok(Event::End(e)) => {
-                    if e.name().into_inner() == b"revision" {
-                        break;
-                    }
+                Ok(Event::End(e)) if e.name().into_inner() == b"revision" => {
+                    break;
                 }
                 Ok(Event::Eof) => return Err(Error::TextNotFound),
                 _ => (),
@@ -20,7 +20,7 @@ fn processed_file(filename: &str) -> String {
 // Load the raw and processed file of the given name, and make sure they are equal
 fn test_full_doc(article_name: &str) {
     let raw = raw_file(article_name);
-    let output = extract(&raw, &article_name);
+    let output = extract(&raw, article_name);
 
     let processed = processed_file(article_name);
 
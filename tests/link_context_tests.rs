@@ -0,0 +1,79 @@
+use wikipedia_parser::extractors::wikitext::{
+    extract,
+    link_context::{self, extract_fallback, extract_link_contexts, Page},
+};
+
+use serde_json::Value;
+use std::fs;
+
+const RAW_ARTICLE_DIR: &str = "resources/test/raw_articles/";
+
+// Load a file from the raw article directory
+fn raw_file(filename: &str) -> Vec<u8> {
+    let filename = RAW_ARTICLE_DIR.to_string() + filename + ".txt";
+    fs::read(filename).expect("Should have been able to read the file")
+}
+
+// Make sure every link's span points at its label in the contents extractor's output
+fn test_link_contexts(article_name: &str) {
+    let raw = raw_file(article_name);
+    let links = extract_link_contexts(&raw, article_name);
+    let tree: Value = serde_json::from_str(&extract(&raw, article_name)).unwrap();
+
+    assert!(!links.is_empty());
+
+    for link in links {
+        assert_eq!(link.section[0], article_name);
+
+        let mut section = &tree;
+        for name in &link.section[1..] {
+            section = section["children"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|child| child["section_name"] == name.as_str())
+                .unwrap_or_else(|| panic!("Section not found: {:?}", link.section));
+        }
+
+        let paragraph = section["text"]
+            .as_str()
+            .unwrap()
+            .lines()
+            .nth(link.paragraph)
+            .unwrap();
+        let label: String = paragraph
+            .chars()
+            .skip(link.start)
+            .take(link.end - link.start)
+            .collect();
+
+        assert_eq!(label, link.label, "{:?}", link);
+    }
+}
+
+#[test]
+fn a() {
+    test_link_contexts("A");
+}
+
+#[test]
+fn achilles() {
+    test_link_contexts("Achilles");
+}
+
+#[test]
+fn apollo_8() {
+    test_link_contexts("Apollo 8");
+}
+
+#[test]
+fn fallback() {
+    let raw = raw_file("A");
+    let page: Page = serde_json::from_str(&extract_fallback(&raw, "A")).unwrap();
+    assert_eq!(page.title, "A");
+    assert!(page.links.is_empty());
+    assert!(page.fallback);
+
+    let page: Value = serde_json::from_str(&link_context::extract(&raw, "A")).unwrap();
+    assert!(page.get("fallback").is_none());
+}